@@ -0,0 +1,455 @@
+//! 插件命令行工具
+//!
+//! 插件作者用于校验、打包、检查与发布插件；部署脚本用于在没有窗口的情况下管理
+//! 应用已安装的插件（与桌面应用读写同一个 plugins 目录）。
+//!
+//! ```text
+//! usefultools-plugin validate [dir] [--json]
+//! usefultools-plugin pack [dir] [--out <dir>]
+//! usefultools-plugin inspect <file.tgz> [--json]
+//! usefultools-plugin publish [dir|file.tgz] --registry <url> [--tag <tag>] [--token <token>]
+//!                            [--plugins-dir <dir>]
+//!
+//! usefultools-plugin registry [--refresh] [--json]
+//! usefultools-plugin list [--json]
+//! usefultools-plugin install <id|package>... [--json]
+//! usefultools-plugin uninstall <id>... [--cascade] [--json]
+//! usefultools-plugin outdated [--json]
+//! ```
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::Mutex;
+use usefultools_lib::error::AppError;
+use usefultools_lib::plugin_manager::manifest::{self, ManifestReport, Severity};
+use usefultools_lib::plugin_manager::pack::{self, Package};
+use usefultools_lib::plugin_manager::{InstallPhase, InstallProgress, PluginMeta, PluginStore};
+
+const USAGE: &str = "用法:
+  usefultools-plugin validate [dir] [--json]        校验 plugin.json 与 bundle 文件
+  usefultools-plugin pack [dir] [--out <dir>]       打包为可安装的 .tgz
+  usefultools-plugin inspect <file.tgz> [--json]    查看 tarball 内容并校验
+  usefultools-plugin publish [dir|file.tgz] --registry <url> [--tag <tag>] [--token <token>]
+                                                    发布到 npm 兼容的 registry（如 Verdaccio）
+
+管理已安装的插件:
+  usefultools-plugin registry [--refresh] [--json]  列出注册表中的插件
+  usefultools-plugin list [--json]                  列出已安装的插件
+  usefultools-plugin install <id|package>... [--json]
+                                                    安装插件（包名表示安装包内全部插件）
+  usefultools-plugin uninstall <id>... [--cascade] [--json]
+                                                    卸载插件（--cascade 同时卸载依赖它的插件）
+  usefultools-plugin outdated [--json]              检查可用更新
+
+publish 未指定 --token 时读取环境变量 NPM_TOKEN，并使用桌面应用中的网络设置（代理、根证书、超时）。
+publish 与管理命令默认使用桌面应用的数据目录，可用 --plugins-dir <dir> 指定其他目录。";
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match run(args).await {
+        Ok(code) => code,
+        Err(e) => {
+            eprintln!("错误: {e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// 解析后的参数：位置参数与 `--key value` / `--flag` 选项
+struct Args {
+    positional: Vec<String>,
+    options: HashMap<String, Option<String>>,
+}
+
+impl Args {
+    /// `value_options` 中的选项需要跟一个值，其余视为开关
+    fn parse(args: Vec<String>, value_options: &[&str]) -> Result<Self, AppError> {
+        let mut positional = Vec::new();
+        let mut options = HashMap::new();
+        let mut iter = args.into_iter();
+        while let Some(arg) = iter.next() {
+            let Some(key) = arg.strip_prefix("--") else {
+                positional.push(arg);
+                continue;
+            };
+            if let Some((key, value)) = key.split_once('=') {
+                options.insert(key.to_string(), Some(value.to_string()));
+            } else if value_options.contains(&key) {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| AppError::invalid_input(format!("--{key} 需要一个值")))?;
+                options.insert(key.to_string(), Some(value));
+            } else {
+                options.insert(key.to_string(), None);
+            }
+        }
+        Ok(Self {
+            positional,
+            options,
+        })
+    }
+
+    fn flag(&self, key: &str) -> bool {
+        self.options.contains_key(key)
+    }
+
+    fn value(&self, key: &str) -> Option<&str> {
+        self.options.get(key).and_then(|v| v.as_deref())
+    }
+
+    /// 第一个位置参数，缺省为当前目录
+    fn path(&self) -> PathBuf {
+        PathBuf::from(self.positional.first().map(String::as_str).unwrap_or("."))
+    }
+}
+
+async fn run(args: Vec<String>) -> Result<ExitCode, AppError> {
+    let mut args = args.into_iter();
+    let Some(command) = args.next() else {
+        eprintln!("{USAGE}");
+        return Ok(ExitCode::FAILURE);
+    };
+    let rest: Vec<String> = args.collect();
+
+    match command.as_str() {
+        "validate" => validate(Args::parse(rest, &[])?).await,
+        "pack" => pack_dir(Args::parse(rest, &["out"])?).await,
+        "inspect" => inspect(Args::parse(rest, &[])?).await,
+        "publish" => {
+            publish(Args::parse(
+                rest,
+                &["registry", "tag", "token", "plugins-dir"],
+            )?)
+            .await
+        }
+        "registry" => registry(Args::parse(rest, &["plugins-dir"])?).await,
+        "list" => list(Args::parse(rest, &["plugins-dir"])?).await,
+        "install" => install(Args::parse(rest, &["plugins-dir"])?).await,
+        "uninstall" => uninstall(Args::parse(rest, &["plugins-dir"])?).await,
+        "outdated" => outdated(Args::parse(rest, &["plugins-dir"])?).await,
+        "help" | "--help" | "-h" => {
+            println!("{USAGE}");
+            Ok(ExitCode::SUCCESS)
+        }
+        other => Err(AppError::invalid_input(format!(
+            "未知命令: {other}\n\n{USAGE}"
+        ))),
+    }
+}
+
+// ── validate ────────────────────────────────────────────────
+
+async fn validate(args: Args) -> Result<ExitCode, AppError> {
+    let dir = args.path();
+    let package_name = match pack::read_package_json(&dir).await {
+        Ok(package_json) => package_json
+            .get("name")
+            .and_then(|v| v.as_str())
+            .map(str::to_string),
+        Err(_) => None,
+    }
+    .unwrap_or_else(|| dir_name(&dir));
+
+    let report = manifest::validate_dir(&dir, &package_name, &HashMap::new()).await?;
+
+    if args.flag("json") {
+        print_json(&report)?;
+    } else {
+        print_diagnostics(&report);
+        if !report.has_errors() {
+            println!("plugin.json 校验通过，共 {} 个插件", report.entries.len());
+        }
+    }
+    Ok(exit_code(&report))
+}
+
+fn dir_name(dir: &Path) -> String {
+    std::fs::canonicalize(dir)
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_default()
+}
+
+// ── pack ────────────────────────────────────────────────────
+
+async fn pack_dir(args: Args) -> Result<ExitCode, AppError> {
+    let package = pack::pack(&args.path()).await?;
+    print_diagnostics(&package.manifest);
+
+    let out_dir = PathBuf::from(args.value("out").unwrap_or("."));
+    tokio::fs::create_dir_all(&out_dir)
+        .await
+        .map_err(|e| AppError::io(format!("无法创建输出目录: {e}")))?;
+    let out = out_dir.join(package.file_name());
+    tokio::fs::write(&out, &package.bytes)
+        .await
+        .map_err(|e| AppError::io(format!("写入 {} 失败: {e}", out.display())))?;
+
+    print_summary(&package);
+    println!("已生成 {}", out.display());
+    Ok(ExitCode::SUCCESS)
+}
+
+// ── inspect ─────────────────────────────────────────────────
+
+async fn inspect(args: Args) -> Result<ExitCode, AppError> {
+    let path = args
+        .positional
+        .first()
+        .ok_or_else(|| AppError::invalid_input("inspect 需要指定 .tgz 文件"))?;
+    let package = load_tarball(Path::new(path)).await?;
+
+    if args.flag("json") {
+        print_json(&package)?;
+    } else {
+        print_summary(&package);
+        print_diagnostics(&package.manifest);
+    }
+    Ok(exit_code(&package.manifest))
+}
+
+async fn load_tarball(path: &Path) -> Result<Package, AppError> {
+    let bytes = tokio::fs::read(path)
+        .await
+        .map_err(|e| AppError::file(format!("读取 {} 失败: {e}", path.display()), &e))?;
+    Package::from_tarball(bytes)
+}
+
+// ── publish ─────────────────────────────────────────────────
+
+async fn publish(args: Args) -> Result<ExitCode, AppError> {
+    let registry = args
+        .value("registry")
+        .ok_or_else(|| AppError::invalid_input("publish 需要指定 --registry <url>"))?;
+    let tag = args.value("tag").unwrap_or("latest");
+    let token = args
+        .value("token")
+        .map(str::to_string)
+        .or_else(|| std::env::var("NPM_TOKEN").ok().filter(|t| !t.is_empty()));
+
+    let path = args.path();
+    let package = if path.is_dir() {
+        pack::pack(&path).await?
+    } else {
+        load_tarball(&path).await?
+    };
+    print_diagnostics(&package.manifest);
+
+    // 与桌面应用使用同一份网络设置
+    let store = store_at(&args)?;
+    let config = store.plugin_config().await;
+    let client = store.http().client(&config.network)?;
+    let tarball_url = pack::publish(&client, registry, &package, tag, token.as_deref()).await?;
+    println!(
+        "已发布 {}@{}（{tag}）\n  {tarball_url}",
+        package.name, package.version
+    );
+    Ok(ExitCode::SUCCESS)
+}
+
+// ── 管理已安装的插件 ────────────────────────────────────────
+
+/// --plugins-dir 指定的 plugins 目录，缺省为桌面应用的数据目录
+fn store_at(args: &Args) -> Result<PluginStore, AppError> {
+    match args.value("plugins-dir") {
+        Some(dir) => Ok(PluginStore::new(dir)),
+        None => PluginStore::open_default(),
+    }
+}
+
+/// 打开 plugins 目录；非 JSON 输出时把安装阶段打印到 stderr
+fn open_store(args: &Args) -> Result<PluginStore, AppError> {
+    let store = store_at(args)?;
+    if args.flag("json") {
+        return Ok(store);
+    }
+    // 同一插件的同一阶段只打印一次，下载过程中的字节进度不打印
+    let last = Mutex::new(None);
+    Ok(store.with_progress(move |progress: &InstallProgress| {
+        if let Some(warning) = &progress.warning {
+            eprintln!("警告 {}: {warning}", progress.plugin_id);
+            return;
+        }
+        let current = Some((progress.plugin_id.clone(), progress.phase));
+        let mut last = last.lock().unwrap();
+        if *last != current {
+            eprintln!("{}: {}", progress.plugin_id, phase_label(progress.phase));
+            *last = current;
+        }
+    }))
+}
+
+fn phase_label(phase: InstallPhase) -> &'static str {
+    match phase {
+        InstallPhase::Resolve => "解析版本",
+        InstallPhase::Download => "下载",
+        InstallPhase::Verify => "校验",
+        InstallPhase::Extract => "解压",
+        InstallPhase::Write => "写入",
+    }
+}
+
+async fn registry(args: Args) -> Result<ExitCode, AppError> {
+    let store = open_store(&args)?;
+    let result = store.fetch_registry(args.flag("refresh")).await?;
+
+    if args.flag("json") {
+        print_json(&result)?;
+    } else {
+        for plugin in &result.plugins {
+            print_plugin(plugin);
+        }
+        for error in &result.errors {
+            eprintln!("警告 {}: {}", error.package_name, error.error);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn list(args: Args) -> Result<ExitCode, AppError> {
+    let store = open_store(&args)?;
+    let mut installed = store.installed_plugins().await?;
+    installed.sort_by(|a, b| a.meta.id.cmp(&b.meta.id));
+
+    if args.flag("json") {
+        print_json(&installed)?;
+    } else {
+        for plugin in &installed {
+            print_plugin(&plugin.meta);
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn install(args: Args) -> Result<ExitCode, AppError> {
+    if args.positional.is_empty() {
+        return Err(AppError::invalid_input("install 需要指定插件 id 或包名"));
+    }
+    let store = open_store(&args)?;
+    let registry = store.fetch_registry(false).await?.plugins;
+
+    // 先全部解析，任一名称找不到时不安装任何插件
+    let mut targets: Vec<&PluginMeta> = Vec::new();
+    for name in &args.positional {
+        let matched: Vec<&PluginMeta> = match registry.iter().find(|p| &p.id == name) {
+            Some(plugin) => vec![plugin],
+            None => registry
+                .iter()
+                .filter(|p| &p.package_name == name)
+                .collect(),
+        };
+        if matched.is_empty() {
+            return Err(AppError::not_found(format!("注册表中没有插件或包 {name}")));
+        }
+        for plugin in matched {
+            if !targets.iter().any(|t| t.id == plugin.id) {
+                targets.push(plugin);
+            }
+        }
+    }
+
+    let mut installed = Vec::new();
+    for plugin in targets {
+        let info = store.install_plugin(plugin.clone()).await?;
+        if !args.flag("json") {
+            println!("已安装 {}@{}", info.meta.id, info.meta.version);
+        }
+        installed.push(info);
+    }
+    if args.flag("json") {
+        print_json(&installed)?;
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn uninstall(args: Args) -> Result<ExitCode, AppError> {
+    if args.positional.is_empty() {
+        return Err(AppError::invalid_input("uninstall 需要指定插件 id"));
+    }
+    let store = open_store(&args)?;
+
+    let mut removed = Vec::new();
+    for id in &args.positional {
+        if removed.contains(id) {
+            continue;
+        }
+        removed.extend(
+            store
+                .uninstall_plugin(id.clone(), args.flag("cascade"))
+                .await?,
+        );
+    }
+    if args.flag("json") {
+        print_json(&removed)?;
+    } else if removed.is_empty() {
+        println!("没有需要卸载的插件");
+    } else {
+        println!("已卸载 {}", removed.join(", "));
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+async fn outdated(args: Args) -> Result<ExitCode, AppError> {
+    let store = open_store(&args)?;
+    let updates = store.check_updates().await?;
+
+    if args.flag("json") {
+        print_json(&updates)?;
+    } else if updates.is_empty() {
+        println!("所有插件均为最新版本");
+    } else {
+        for update in &updates {
+            println!(
+                "{}  {} → {}  ({:?})",
+                update.plugin.id, update.current_version, update.plugin.version, update.kind
+            );
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+// ── 输出 ────────────────────────────────────────────────────
+
+fn print_plugin(plugin: &PluginMeta) {
+    println!(
+        "{}@{}  {}  ({})",
+        plugin.id, plugin.version, plugin.title, plugin.package_name
+    );
+}
+
+fn print_json(value: &impl serde::Serialize) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(value)
+        .map_err(|e| AppError::internal(format!("序列化失败: {e}")))?;
+    println!("{json}");
+    Ok(())
+}
+
+fn print_diagnostics(report: &ManifestReport) {
+    for diagnostic in &report.diagnostics {
+        let label = match diagnostic.severity {
+            Severity::Error => "错误",
+            Severity::Warning => "警告",
+        };
+        eprintln!("{label} {}: {}", diagnostic.path, diagnostic.message);
+    }
+}
+
+fn print_summary(package: &Package) {
+    println!("{}@{}", package.name, package.version);
+    for file in &package.files {
+        println!("  {:>10}  {}", file.size, file.path);
+    }
+    println!("大小: {} 字节", package.size);
+    println!("integrity: {}", package.integrity);
+    println!("shasum: {}", package.shasum);
+}
+
+fn exit_code(report: &ManifestReport) -> ExitCode {
+    if report.has_errors() {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
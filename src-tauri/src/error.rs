@@ -0,0 +1,182 @@
+use crate::plugin_manager::manifest::Diagnostic;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// ── 命令错误 ────────────────────────────────────────────────
+//
+// 所有 Tauri 命令失败时返回 AppError，序列化为
+// `{ code, message, context: { package?, pluginId?, url?, status?, diagnostics? } }`。
+// code 是稳定的错误分类，前端据此决定如何处理；message 为展示给用户的说明。
+
+/// 错误分类（序列化为 snake_case，新增分类不会改变已有的值）
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// 无法连接 registry（DNS、连接失败、超时、代理或证书问题）
+    Network,
+    /// registry 返回了非成功的 HTTP 状态（见 context.status）
+    Http,
+    /// registry 拒绝访问（401 / 403），需要配置或更新凭据
+    Unauthorized,
+    /// 包、版本、插件、bundle 或文件不存在
+    NotFound,
+    /// tarball 完整性校验失败
+    Integrity,
+    /// tarball 损坏或包含不安全的内容
+    InvalidPackage,
+    /// plugin.json / package.json 不合法
+    InvalidManifest,
+    /// registry 响应或本地文件无法解析
+    Parse,
+    /// 参数或设置不合法（插件 id、代理地址、根证书等）
+    InvalidInput,
+    /// 依赖缺失、循环，或仍被其他插件依赖
+    Dependency,
+    /// 插件已停用
+    Disabled,
+    /// 插件正在安装中
+    Busy,
+    /// 安装已取消
+    Cancelled,
+    /// 读写本地文件失败
+    Io,
+    /// 不允许的操作（如不在白名单中的 Shell 命令）
+    Forbidden,
+    /// 其他内部错误
+    Internal,
+}
+
+/// 错误涉及的对象，未知的字段不序列化
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct ErrorContext {
+    /// npm 包名
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub package: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub plugin_id: Option<String>,
+    /// 请求的地址
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// registry 返回的 HTTP 状态码
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+    /// plugin.json 校验的全部错误与警告（带 JSON 路径）
+    ///
+    /// 装箱以免 AppError 超过 clippy::result_large_err 的上限
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub diagnostics: Box<Vec<Diagnostic>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct AppError {
+    pub code: ErrorCode,
+    pub message: String,
+    pub context: ErrorContext,
+}
+
+impl AppError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            context: ErrorContext::default(),
+        }
+    }
+
+    pub fn network(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Network, message)
+    }
+
+    /// registry 返回非成功状态：401 / 403 为 Unauthorized，404 / 410 为 NotFound，其余为 Http
+    pub fn http(status: u16, message: impl Into<String>) -> Self {
+        let code = match status {
+            401 | 403 => ErrorCode::Unauthorized,
+            404 | 410 => ErrorCode::NotFound,
+            _ => ErrorCode::Http,
+        };
+        Self::new(code, message).with_status(status)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn parse(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Parse, message)
+    }
+
+    pub fn io(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Io, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    /// 读取用户指定的文件失败：文件不存在时为 NotFound，其余为 Io
+    pub fn file(message: impl Into<String>, e: &std::io::Error) -> Self {
+        let code = match e.kind() {
+            std::io::ErrorKind::NotFound => ErrorCode::NotFound,
+            _ => ErrorCode::Io,
+        };
+        Self::new(code, message)
+    }
+
+    // 补充上下文：已有的字段不会被覆盖，最先记录的（离出错位置最近的）为准
+
+    pub fn with_package(mut self, package: impl Into<String>) -> Self {
+        self.context.package.get_or_insert_with(|| package.into());
+        self
+    }
+
+    pub fn with_plugin(mut self, plugin_id: impl Into<String>) -> Self {
+        self.context
+            .plugin_id
+            .get_or_insert_with(|| plugin_id.into());
+        self
+    }
+
+    pub fn with_url(mut self, url: impl Into<String>) -> Self {
+        self.context.url.get_or_insert_with(|| url.into());
+        self
+    }
+
+    pub fn with_status(mut self, status: u16) -> Self {
+        self.context.status.get_or_insert(status);
+        self
+    }
+
+    pub fn with_diagnostics(mut self, diagnostics: Vec<Diagnostic>) -> Self {
+        if self.context.diagnostics.is_empty() {
+            *self.context.diagnostics = diagnostics;
+        }
+        self
+    }
+
+    /// 在说明前加上前缀（如出错的 registry），分类与上下文不变
+    pub fn prefixed(mut self, prefix: impl fmt::Display) -> Self {
+        self.message = format!("{prefix}: {}", self.message);
+        self
+    }
+
+    /// 改写说明与地址中的文本（用于隐去凭据）
+    pub fn map_text(mut self, f: impl Fn(&str) -> String) -> Self {
+        self.message = f(&self.message);
+        self.context.url = self.context.url.as_deref().map(&f);
+        self
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for AppError {}
@@ -1,4 +1,5 @@
-mod plugin_manager;
+pub mod error;
+pub mod plugin_manager;
 mod system_commands;
 
 use local_ip_address::local_ip;
@@ -23,21 +24,46 @@ pub fn run() {
         .plugin(tauri_plugin_http::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
+        .manage(plugin_manager::InstallTasks::default())
+        .manage(plugin_manager::HttpClients::default())
+        .setup(|app| {
+            plugin_manager::recover_interrupted_installs(app.handle());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             greet,
             get_local_ip,
             plugin_manager::fetch_plugin_registry,
             plugin_manager::install_plugin,
+            plugin_manager::install_plugins,
+            plugin_manager::install_plugin_from_file,
+            plugin_manager::export_plugin_lockfile,
+            plugin_manager::import_plugin_lockfile,
+            plugin_manager::install_plugin_version,
+            plugin_manager::cancel_plugin_install,
+            plugin_manager::list_plugin_versions,
+            plugin_manager::set_plugin_pin,
+            plugin_manager::set_plugin_enabled,
+            plugin_manager::rollback_plugin,
+            plugin_manager::clear_plugin_cache,
             plugin_manager::uninstall_plugin,
+            plugin_manager::get_plugin_dependency_graph,
             plugin_manager::get_installed_plugins,
+            plugin_manager::repair_plugin_index,
+            plugin_manager::take_plugin_recovery_report,
             plugin_manager::get_plugin_bundle_path,
             plugin_manager::read_plugin_bundle,
             plugin_manager::read_local_bundle,
             plugin_manager::read_local_plugin_json,
+            plugin_manager::validate_local_plugin_json,
             plugin_manager::check_plugin_updates,
             plugin_manager::fetch_package_by_name,
             plugin_manager::get_plugin_config,
             plugin_manager::set_plugin_config,
+            plugin_manager::list_registry_auth,
+            plugin_manager::set_registry_auth,
+            plugin_manager::clear_registry_auth,
+            plugin_manager::import_npmrc_auth,
             system_commands::get_cpu_info,
             system_commands::get_memory_info,
             system_commands::get_disk_info,
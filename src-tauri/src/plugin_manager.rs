@@ -1,8 +1,36 @@
+use crate::error::{AppError, ErrorCode};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 use std::time::{SystemTime, UNIX_EPOCH};
 use tauri::Manager;
 
+pub mod auth;
+mod cache;
+pub mod client;
+mod dependency;
+pub mod index;
+mod integrity;
+pub mod lockfile;
+pub mod manifest;
+pub mod pack;
+mod progress;
+pub mod search;
+pub mod staging;
+mod state;
+mod store;
+pub mod tarball;
+mod version;
+
+pub use auth::{RegistryAuth, RegistryAuthList, RegistryAuthSummary};
+pub use client::{HttpClient, HttpClients};
+use client::{NetworkConfig, RegistryClient};
+pub use dependency::DependencyGraph;
+pub use lockfile::{Lockfile, LockfileDiff};
+pub use progress::{InstallPhase, InstallProgress, InstallTasks};
+pub use staging::RecoveryReport;
+pub use store::PluginStore;
+pub use version::UpdateKind;
+
 // ── 插件元数据（单个工具） ──────────────────────────────────
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -20,8 +48,20 @@ pub struct PluginMeta {
     pub text_color: Option<String>,
     pub categories: Vec<String>,
     pub requires: Vec<String>,
+    /// 依赖的其他插件 id，安装时会先安装这些插件
+    #[serde(default)]
+    pub dependencies: Vec<String>,
     /// npm 包名（如 usefultools-plugin-official）
     pub package_name: String,
+    /// 插件来自的 registry 地址，安装与更新时使用同一个 registry
+    #[serde(default)]
+    pub registry: Option<String>,
+    /// 非 registry 的安装来源（如从本地文件安装时为 "local file"）
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// 安装时 tarball 的 SRI 哈希（sha512），用于生成锁文件
+    #[serde(default)]
+    pub integrity: Option<String>,
     /// bundle 文件名（如 json-formatter.mjs）
     pub bundle_file: String,
     pub downloads: Option<u64>,
@@ -36,10 +76,21 @@ pub struct PluginMeta {
 #[serde(rename_all = "camelCase")]
 pub struct InstalledPluginInfo {
     pub meta: PluginMeta,
+    /// 首次安装时间（见 state.json）
     pub installed_at: u64,
+    /// 最近一次安装、更新或回滚的时间
     pub updated_at: u64,
+    /// 最近一次更新前的版本
+    #[serde(default)]
+    pub previous_version: Option<String>,
     pub local_bundle_path: String,
     pub enabled: bool,
+    /// 锁定的版本（见 pin.json），锁定后不参与更新检查
+    #[serde(default)]
+    pub pinned_version: Option<String>,
+    /// 可回滚到的上一个版本
+    #[serde(default)]
+    pub backup_version: Option<String>,
 }
 
 // ── npm 包中的 plugin.json 格式 ─────────────────────────────
@@ -61,25 +112,39 @@ pub struct PluginJsonEntry {
     pub categories: Vec<String>,
     #[serde(default)]
     pub requires: Vec<String>,
+    /// 依赖的其他插件 id（`requires` 为所需权限，不参与依赖解析）
+    #[serde(default)]
+    pub dependencies: Vec<String>,
     /// bundle 文件相对路径（如 dist/json-formatter.mjs）
     pub bundle: String,
 }
 
-/// plugin.json 根结构（支持单工具或多工具）
-#[derive(Serialize, Deserialize, Clone, Debug)]
-#[serde(untagged)]
-pub enum PluginJson {
-    /// 多工具包：{ "plugins": [...] }
-    Multi { plugins: Vec<PluginJsonEntry> },
-    /// 单工具包：直接是一个 PluginJsonEntry
-    Single(PluginJsonEntry),
-}
-
-impl PluginJson {
-    pub fn into_entries(self) -> Vec<PluginJsonEntry> {
-        match self {
-            PluginJson::Multi { plugins } => plugins,
-            PluginJson::Single(entry) => vec![entry],
+impl PluginJsonEntry {
+    /// 转换为插件元数据
+    pub fn into_meta(self, package_name: &str) -> PluginMeta {
+        PluginMeta {
+            id: self.id,
+            version: self.version,
+            author: self.author,
+            homepage: self.homepage,
+            icon: self.icon,
+            title: self.title,
+            subtitle: self.subtitle,
+            description: self.description,
+            bg_color: self.bg_color,
+            text_color: self.text_color,
+            categories: self.categories,
+            requires: self.requires,
+            dependencies: self.dependencies,
+            package_name: package_name.to_string(),
+            registry: None,
+            origin: None,
+            integrity: None,
+            bundle_file: self.bundle,
+            downloads: None,
+            rating: None,
+            updated_at: None,
+            created_at: None,
         }
     }
 }
@@ -89,6 +154,8 @@ impl PluginJson {
 #[derive(Deserialize, Debug)]
 struct NpmSearchResponse {
     objects: Vec<NpmSearchObject>,
+    /// 匹配的总数，用于分页
+    total: Option<u64>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -99,30 +166,12 @@ struct NpmSearchObject {
 #[derive(Deserialize, Debug)]
 struct NpmPackageInfo {
     name: String,
-    version: String,
-    description: Option<String>,
-    date: Option<String>,
-    links: Option<NpmLinks>,
-}
-
-#[derive(Deserialize, Debug)]
-struct NpmLinks {
-    npm: Option<String>,
-    homepage: Option<String>,
-    repository: Option<String>,
 }
 
 /// npm 包的 package.json（从 tarball 中解析）
 #[derive(Deserialize, Debug)]
 struct NpmPackageJson {
     name: String,
-    version: String,
-    dist: Option<NpmDist>,
-}
-
-#[derive(Deserialize, Debug)]
-struct NpmDist {
-    tarball: Option<String>,
 }
 
 /// npm registry 单包信息（GET /<package>）
@@ -132,17 +181,22 @@ struct NpmPackageDetail {
     #[serde(rename = "dist-tags")]
     dist_tags: Option<std::collections::HashMap<String, String>>,
     versions: Option<std::collections::HashMap<String, NpmVersionDetail>>,
+    /// 各版本的发布时间（ISO 8601）
+    time: Option<std::collections::HashMap<String, String>>,
 }
 
 #[derive(Deserialize, Debug)]
 struct NpmVersionDetail {
-    version: String,
     dist: Option<NpmVersionDist>,
 }
 
 #[derive(Deserialize, Debug)]
 struct NpmVersionDist {
     tarball: Option<String>,
+    /// SRI 格式的哈希（如 sha512-xxxx）
+    integrity: Option<String>,
+    /// tarball 的 SHA-1（十六进制）
+    shasum: Option<String>,
 }
 
 // ── 注册表缓存 ─────────────────────────────────────────────
@@ -153,18 +207,82 @@ pub struct RegistryCache {
     pub fetched_at: u64,
     pub ttl: u64,
     pub plugins: Vec<PluginMeta>,
+    /// 各包的验证信息，用于下次刷新时发送条件请求
+    #[serde(default)]
+    pub packages: std::collections::HashMap<String, PackageCacheEntry>,
+    /// 上次刷新时解析失败的包
+    #[serde(default)]
+    pub errors: Vec<PackageError>,
 }
 
-// ── 辅助函数 ────────────────────────────────────────────────
+/// 解析失败的包
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageError {
+    /// 包名；registry 搜索失败或结果不完整时为该 registry 的地址
+    pub package_name: String,
+    pub error: AppError,
+}
 
-pub fn plugins_dir(app: &tauri::AppHandle) -> Result<PathBuf, String> {
-    let base = app
-        .path()
-        .app_data_dir()
-        .map_err(|e| format!("无法获取应用数据目录: {e}"))?;
-    Ok(base.join("plugins"))
+/// plugin.json 中有警告的包（包中的插件仍可使用）
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageWarning {
+    pub package_name: String,
+    pub diagnostics: Vec<manifest::Diagnostic>,
+}
+
+/// fetch_plugin_registry 的返回值
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryFetchResult {
+    pub plugins: Vec<PluginMeta>,
+    /// 解析失败的包（失败的包若有上次的结果则继续沿用）
+    pub errors: Vec<PackageError>,
+    /// plugin.json 中有警告的包
+    pub warnings: Vec<PackageWarning>,
+}
+
+/// 各包 plugin.json 中的警告（按包名排序）
+fn package_warnings(
+    packages: &std::collections::HashMap<String, PackageCacheEntry>,
+) -> Vec<PackageWarning> {
+    let mut warnings: Vec<PackageWarning> = packages
+        .iter()
+        .filter(|(_, entry)| !entry.warnings.is_empty())
+        .map(|(name, entry)| PackageWarning {
+            package_name: name.clone(),
+            diagnostics: entry.warnings.clone(),
+        })
+        .collect();
+    warnings.sort_by(|a, b| a.package_name.cmp(&b.package_name));
+    warnings
+}
+
+/// 一次注册表刷新的结果
+struct RegistryCrawl {
+    plugins: Vec<PluginMeta>,
+    packages: std::collections::HashMap<String, PackageCacheEntry>,
+    errors: Vec<PackageError>,
 }
 
+/// 单个包上次解析的结果
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PackageCacheEntry {
+    pub registry: String,
+    pub channel: String,
+    pub version: String,
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+    pub plugins: Vec<PluginMeta>,
+    /// plugin.json 中的警告
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<manifest::Diagnostic>,
+}
+
+// ── 辅助函数 ────────────────────────────────────────────────
+
 pub fn now_ms() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -172,10 +290,65 @@ pub fn now_ms() -> u64 {
         .as_millis() as u64
 }
 
+/// 校验插件 id：仅允许字母、数字、- 和 _，以字母或数字开头，最长 64 个字符
+///
+/// id 直接用作插件目录名，必须保证无法逃出 plugins 目录。
+pub fn validate_plugin_id(id: &str) -> Result<(), AppError> {
+    let valid = !id.is_empty()
+        && id.len() <= 64
+        && id.chars().next().is_some_and(|c| c.is_ascii_alphanumeric())
+        && id
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(AppError::invalid_input(format!("插件 id 不合法: {id}")).with_plugin(id))
+    }
+}
+
 const DEFAULT_NPM_REGISTRY: &str = "https://registry.npmjs.org";
 const NPM_SEARCH_KEYWORD: &str = "usefultools-plugin";
 const OFFICIAL_PACKAGE: &str = "usefultools-plugin-official";
 const DEFAULT_TTL: u64 = 3_600_000; // 1 小时
+const DEFAULT_CHANNEL: &str = "latest";
+const DEFAULT_MAX_CONCURRENCY: usize = 6;
+
+/// 启动时清理或完成上次中断的安装 / 更新
+pub fn recover_interrupted_installs(app: &tauri::AppHandle) {
+    if let Ok(store) = PluginStore::from_app(app) {
+        store.recover_interrupted_installs();
+    }
+}
+
+impl PluginStore {
+    /// 恢复中断的安装，结果保存下来供前端读取
+    pub fn recover_interrupted_installs(&self) -> RecoveryReport {
+        let dir = self.plugins_dir();
+        let report = staging::recover(dir);
+        if !report.restored.is_empty() || !report.removed.is_empty() {
+            // 恢复改变了插件目录，索引下次读取时重建
+            index::invalidate(dir);
+        }
+        if !report.is_empty() {
+            // 结果只用于提示用户，保存失败不影响启动
+            let _ = staging::save_report(dir, &report);
+        }
+        report
+    }
+
+    /// 取出上次启动时恢复中断安装的结果（只返回一次），没有恢复任何内容时返回 None
+    pub async fn take_recovery_report(&self) -> Result<Option<RecoveryReport>, AppError> {
+        staging::take_report(self.plugins_dir()).await
+    }
+}
+
+#[tauri::command]
+pub async fn take_plugin_recovery_report(
+    app: tauri::AppHandle,
+) -> Result<Option<RecoveryReport>, AppError> {
+    PluginStore::from_app(&app)?.take_recovery_report().await
+}
 
 // ── 用户配置 ────────────────────────────────────────────────
 
@@ -184,66 +357,205 @@ const DEFAULT_TTL: u64 = 3_600_000; // 1 小时
 pub struct PluginConfig {
     /// npm registry 地址，默认 https://registry.npmjs.org
     pub registry: String,
+    /// 按优先级排列的 registry 列表，为空时只使用 registry
+    #[serde(default)]
+    pub registries: Vec<RegistryConfig>,
+    /// 全局发布渠道（npm dist-tag，如 latest / beta / next），默认 latest
+    #[serde(default = "default_channel")]
+    pub channel: String,
+    /// 按包名覆盖的发布渠道
+    #[serde(default)]
+    pub package_channels: std::collections::HashMap<String, String>,
+    /// 刷新注册表时同时解析的包数量上限
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+    /// 代理、根证书、超时与重试等网络设置
+    #[serde(default)]
+    pub network: NetworkConfig,
+}
+
+/// registries 中的一项
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct RegistryConfig {
+    /// registry 地址
+    pub url: String,
+    /// 映射到该 registry 的 scope（如 @ourco 或 @ourco/*），为空表示通用 registry
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+impl RegistryConfig {
+    fn serves_scope(&self, scope: &str) -> bool {
+        self.scopes.iter().any(|s| {
+            let s = s.trim().trim_end_matches('*').trim_end_matches('/');
+            s.trim_start_matches('@') == scope.trim_start_matches('@')
+        })
+    }
+}
+
+fn default_channel() -> String {
+    DEFAULT_CHANNEL.to_string()
+}
+
+fn default_max_concurrency() -> usize {
+    DEFAULT_MAX_CONCURRENCY
 }
 
 impl Default for PluginConfig {
     fn default() -> Self {
         Self {
             registry: DEFAULT_NPM_REGISTRY.to_string(),
+            registries: Vec::new(),
+            channel: default_channel(),
+            package_channels: std::collections::HashMap::new(),
+            max_concurrency: default_max_concurrency(),
+            network: NetworkConfig::default(),
+        }
+    }
+}
+
+impl PluginConfig {
+    /// 获取包实际使用的渠道（包级覆盖优先），stable 视为 latest
+    pub fn channel_for(&self, package_name: &str) -> &str {
+        let channel = self
+            .package_channels
+            .get(package_name)
+            .unwrap_or(&self.channel)
+            .trim();
+        match channel {
+            "" | "stable" => DEFAULT_CHANNEL,
+            other => other,
+        }
+    }
+
+    /// 实际生效的 registry 列表（按优先级，地址已去掉末尾的 /）
+    fn registry_list(&self) -> Vec<RegistryConfig> {
+        let list = if self.registries.is_empty() {
+            vec![RegistryConfig {
+                url: self.registry.clone(),
+                scopes: Vec::new(),
+            }]
+        } else {
+            self.registries.clone()
+        };
+        list.into_iter()
+            .map(|r| RegistryConfig {
+                url: r.url.trim().trim_end_matches('/').to_string(),
+                ..r
+            })
+            .filter(|r| !r.url.is_empty())
+            .collect()
+    }
+
+    /// 包可以从哪些 registry 获取（按尝试顺序）
+    ///
+    /// scoped 包优先使用映射了该 scope 的 registry，再回退到通用 registry；
+    /// 只映射了其他 scope 的 registry 不会被使用。
+    pub fn registries_for(&self, package_name: &str) -> Vec<String> {
+        let list = self.registry_list();
+        let scope = package_name
+            .strip_prefix('@')
+            .and_then(|rest| rest.split_once('/'))
+            .map(|(scope, _)| scope);
+
+        let mapped = list
+            .iter()
+            .filter(|r| scope.is_some_and(|scope| r.serves_scope(scope)));
+        let general = list.iter().filter(|r| r.scopes.is_empty());
+
+        let mut urls: Vec<String> = Vec::new();
+        for r in mapped.chain(general) {
+            if !urls.contains(&r.url) {
+                urls.push(r.url.clone());
+            }
         }
+        urls
+    }
+}
+
+/// 基于共享 HTTP 客户端、带上已保存凭据和重试策略的 registry 客户端
+async fn registry_client(
+    store: &PluginStore,
+    config: &PluginConfig,
+) -> Result<RegistryClient, AppError> {
+    let http = store.http().client(&config.network)?;
+    let credentials = auth::load(store.plugins_dir()).await.credentials;
+    Ok(RegistryClient::new(http, credentials).with_retry(&config.network))
+}
+
+/// 读取凭据，仅用于隐去错误信息
+async fn load_credentials(store: &PluginStore) -> auth::Credentials {
+    auth::load(store.plugins_dir()).await.credentials
+}
+
+/// 隐去返回给前端的错误信息中的凭据
+async fn redact_err<T>(store: &PluginStore, result: Result<T, AppError>) -> Result<T, AppError> {
+    match result {
+        Ok(value) => Ok(value),
+        Err(e) => Err(load_credentials(store).await.redact_error(e)),
     }
 }
 
 /// 读取配置文件，不存在则返回默认值
-async fn load_config(app: &tauri::AppHandle) -> PluginConfig {
-    let dir = match plugins_dir(app) {
-        Ok(d) => d,
-        Err(_) => return PluginConfig::default(),
-    };
-    let config_path = dir.join("config.json");
+async fn load_config(store: &PluginStore) -> PluginConfig {
+    let config_path = store.plugins_dir().join("config.json");
     match tokio::fs::read_to_string(&config_path).await {
         Ok(content) => serde_json::from_str(&content).unwrap_or_default(),
         Err(_) => PluginConfig::default(),
     }
 }
 
+impl PluginStore {
+    /// 读取插件配置，不存在时返回默认值
+    pub async fn plugin_config(&self) -> PluginConfig {
+        load_config(self).await
+    }
+}
+
 #[tauri::command]
-pub async fn get_plugin_config(app: tauri::AppHandle) -> Result<PluginConfig, String> {
-    Ok(load_config(&app).await)
+pub async fn get_plugin_config(app: tauri::AppHandle) -> Result<PluginConfig, AppError> {
+    Ok(PluginStore::from_app(&app)?.plugin_config().await)
+}
+
+impl PluginStore {
+    pub async fn set_plugin_config(&self, config: PluginConfig) -> Result<(), AppError> {
+        let dir = self.plugins_dir();
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| AppError::io(format!("无法创建插件目录: {e}")))?;
+
+        // 保存前先检查代理地址与根证书是否有效
+        config.network.build_client()?;
+
+        // registry 或渠道变化后，缓存中的版本已不可信，标记为过期
+        let previous = load_config(self).await;
+        if previous.registry != config.registry
+            || previous.registries != config.registries
+            || previous.channel != config.channel
+            || previous.package_channels != config.package_channels
+        {
+            expire_cache(&dir.join("registry-cache.json")).await;
+        }
+
+        let json = serde_json::to_string_pretty(&config)
+            .map_err(|e| AppError::internal(format!("序列化配置失败: {e}")))?;
+        tokio::fs::write(dir.join("config.json"), json)
+            .await
+            .map_err(|e| AppError::io(format!("写入配置失败: {e}")))
+    }
 }
 
 #[tauri::command]
 pub async fn set_plugin_config(
     app: tauri::AppHandle,
     config: PluginConfig,
-) -> Result<(), String> {
-    let dir = plugins_dir(&app)?;
-    tokio::fs::create_dir_all(&dir)
-        .await
-        .map_err(|e| format!("无法创建插件目录: {e}"))?;
-    let json = serde_json::to_string_pretty(&config)
-        .map_err(|e| format!("序列化配置失败: {e}"))?;
-    tokio::fs::write(dir.join("config.json"), json)
-        .await
-        .map_err(|e| format!("写入配置失败: {e}"))
+) -> Result<(), AppError> {
+    PluginStore::from_app(&app)?.set_plugin_config(config).await
 }
 
 // ── 从 npm 搜索并解析插件注册表 ─────────────────────────────
 
-/// 执行一次 npm search 请求，返回包名列表
-async fn npm_search(client: &reqwest::Client, registry: &str, query: &str) -> Vec<String> {
-    let url = format!("{}/-/v1/search?text={}&size=100", registry, query);
-    let resp = match client.get(&url).send().await {
-        Ok(r) if r.status().is_success() => r,
-        _ => return Vec::new(),
-    };
-    let data: NpmSearchResponse = match resp.json().await {
-        Ok(d) => d,
-        Err(_) => return Vec::new(),
-    };
-    data.objects.into_iter().map(|o| o.package.name).collect()
-}
-
 /// 判断包名是否为 usefultools 插件
 /// 支持: usefultools-plugin-xxx 或 @scope/usefultools-plugin-xxx
 fn is_usefultools_package(name: &str) -> bool {
@@ -257,20 +569,61 @@ fn is_usefultools_package(name: &str) -> bool {
     false
 }
 
-async fn fetch_npm_plugins(registry: &str) -> Result<Vec<PluginMeta>, String> {
-    let client = reqwest::Client::new();
-    let mut all_plugins: Vec<PluginMeta> = Vec::new();
-
-    // 双重搜索：keyword 搜索 + 包名文本搜索，合并去重
-    let keyword_results = npm_search(&client, registry, &format!("keywords:{}", NPM_SEARCH_KEYWORD)).await;
-    let text_results = npm_search(&client, registry, NPM_SEARCH_KEYWORD).await;
+/// 搜索并解析所有插件包
+///
+/// 各包并发解析，同时进行的数量受 `max_concurrency` 限制。
+/// 传入上次的缓存时，对每个包发送条件请求：304 或渠道版本未变化的包直接复用
+/// 缓存中的插件列表，只有版本变化的包才会重新下载 tarball。
+async fn fetch_npm_plugins(
+    plugins_dir: &std::path::Path,
+    client: &RegistryClient,
+    config: &PluginConfig,
+    previous: Option<&RegistryCache>,
+) -> Result<RegistryCrawl, AppError> {
+    // 在每个 registry 上搜索；无法访问的 registry 沿用上次从它解析到的包
+    // 搜索失败或不完整时按 registry 记入错误报告
+    let mut found: Vec<String> = Vec::new();
+    let mut search_errors = Vec::new();
+    let mut search_error = None;
+    for registry in config.registry_list() {
+        match search_registry(client, &registry.url).await {
+            Ok((names, warnings)) => {
+                found.extend(names);
+                search_errors.extend(warnings.into_iter().map(|error| PackageError {
+                    package_name: registry.url.clone(),
+                    error: client.redact(error),
+                }));
+            }
+            Err(e) => {
+                search_errors.push(PackageError {
+                    package_name: registry.url.clone(),
+                    error: client.redact(e.clone()),
+                });
+                if let Some(cache) = previous {
+                    found.extend(
+                        cache
+                            .packages
+                            .iter()
+                            .filter(|(_, entry)| entry.registry == registry.url)
+                            .map(|(name, _)| name.clone()),
+                    );
+                }
+                search_error = Some(e);
+            }
+        }
+    }
+    if found.is_empty() {
+        if let Some(e) = search_error {
+            return Err(e);
+        }
+    }
 
     let mut seen = std::collections::HashSet::new();
     let mut package_names: Vec<String> = Vec::new();
 
-    // 合并两次搜索结果，去重，支持 scoped 和非 scoped 包名
-    for name in keyword_results.into_iter().chain(text_results.into_iter()) {
-        if is_usefultools_package(&name) && seen.insert(name.clone()) {
+    // 合并各 registry 的搜索结果，去重
+    for name in found {
+        if seen.insert(name.clone()) {
             // 官方包排在最前面
             if name == OFFICIAL_PACKAGE {
                 package_names.insert(0, name);
@@ -280,553 +633,2760 @@ async fn fetch_npm_plugins(registry: &str) -> Result<Vec<PluginMeta>, String> {
         }
     }
 
-    // 逐个包获取 tarball 并解析 plugin.json
-    for pkg_name in &package_names {
-        match fetch_package_plugins(&client, registry, pkg_name).await {
-            Ok(plugins) => all_plugins.extend(plugins),
-            Err(e) => {
-                eprintln!("解析包 {} 失败: {}", pkg_name, e);
-                continue; // 单个包失败不影响其他包
+    // 并发获取各包的 tarball 并解析 plugin.json
+    let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new(config.max_concurrency.max(1)));
+    let mut tasks = tokio::task::JoinSet::new();
+    for (index, pkg_name) in package_names.iter().enumerate() {
+        let channel = config.channel_for(pkg_name).to_string();
+        let known = previous
+            .and_then(|cache| cache.packages.get(pkg_name))
+            .filter(|entry| entry.channel == channel)
+            .cloned();
+        let semaphore = semaphore.clone();
+        let client = client.clone();
+        let plugins_dir = plugins_dir.to_path_buf();
+        let registries = config.registries_for(pkg_name);
+        let pkg_name = pkg_name.clone();
+
+        tasks.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let result = refresh_package_plugins(
+                &plugins_dir,
+                &client,
+                &registries,
+                &pkg_name,
+                &channel,
+                known.as_ref(),
+            )
+            .await;
+            (index, known, result)
+        });
+    }
+
+    let mut results = Vec::with_capacity(package_names.len());
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok(result) => results.push(result),
+            Err(e) => return Err(AppError::internal(format!("解析插件包的任务异常退出: {e}"))),
+        }
+    }
+    // 保持搜索结果的顺序（官方包在前）
+    results.sort_by_key(|(index, _, _)| *index);
+
+    let mut crawl = RegistryCrawl {
+        plugins: Vec::new(),
+        packages: std::collections::HashMap::new(),
+        errors: search_errors,
+    };
+    for (index, known, result) in results {
+        let pkg_name = &package_names[index];
+        // 单个包失败不影响其他包，有上次结果时继续沿用
+        let entry = match result {
+            Ok(entry) => Some(entry),
+            Err(error) => {
+                crawl.errors.push(PackageError {
+                    package_name: pkg_name.clone(),
+                    error: client.redact(error),
+                });
+                known
             }
+        };
+        if let Some(entry) = entry {
+            crawl.plugins.extend(entry.plugins.iter().cloned());
+            crawl.packages.insert(pkg_name.clone(), entry);
         }
     }
 
-    Ok(all_plugins)
+    Ok(crawl)
 }
 
-/// 从单个 npm 包中提取所有插件元数据
-async fn fetch_package_plugins(
-    client: &reqwest::Client,
+/// 在单个 registry 上执行双重搜索：keyword 搜索 + 包名文本搜索，合并去重
+///
+/// 只有一次搜索失败或搜索结果不完整时，返回已取得的包名和相应的警告
+async fn search_registry(
+    client: &RegistryClient,
     registry: &str,
-    package_name: &str,
-) -> Result<Vec<PluginMeta>, String> {
-    // 获取包详情，拿到 tarball URL
-    let detail_url = format!("{}/{}", registry, package_name);
-    let detail_resp = client
-        .get(&detail_url)
-        .send()
-        .await
-        .map_err(|e| format!("获取包详情失败: {e}"))?;
+) -> Result<(Vec<String>, Vec<AppError>), AppError> {
+    let keyword_query = format!("keywords:{}", NPM_SEARCH_KEYWORD);
+    let (keyword, text) = tokio::join!(
+        search::search_all(client, registry, &keyword_query),
+        search::search_all(client, registry, NPM_SEARCH_KEYWORD),
+    );
+
+    let mut names = Vec::new();
+    let mut warnings = Vec::new();
+    match (keyword, text) {
+        // 两次搜索都失败时视为无法访问 registry
+        (Err(e), Err(_)) => return Err(e),
+        (keyword, text) => {
+            for result in [keyword, text] {
+                match result {
+                    Ok(results) => {
+                        names.extend(results.names);
+                        warnings.extend(results.incomplete);
+                    }
+                    Err(e) => warnings.push(e),
+                }
+            }
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
+    // 支持 scoped 和非 scoped 包名
+    names.retain(|name| is_usefultools_package(name) && seen.insert(name.clone()));
+    Ok((names, warnings))
+}
 
-    if !detail_resp.status().is_success() {
-        return Err(format!("包 {} 不存在或请求失败", package_name));
+/// 按优先级依次尝试各 registry 刷新单个包，前一个不可用时回退到下一个
+async fn refresh_package_plugins(
+    plugins_dir: &std::path::Path,
+    client: &RegistryClient,
+    registries: &[String],
+    package_name: &str,
+    channel: &str,
+    known: Option<&PackageCacheEntry>,
+) -> Result<PackageCacheEntry, AppError> {
+    let mut errors = Vec::new();
+    for registry in registries {
+        // 验证信息只对同一个 registry 有效
+        let known = known.filter(|entry| &entry.registry == registry);
+        match refresh_from_registry(plugins_dir, client, registry, package_name, channel, known)
+            .await
+        {
+            Ok(entry) => return Ok(entry),
+            Err(e) => errors.push(e.prefixed(registry)),
+        }
     }
+    Err(all_registries_failed(package_name, errors))
+}
 
-    let detail: NpmPackageDetail = detail_resp
-        .json()
-        .await
-        .map_err(|e| format!("解析包详情失败: {e}"))?;
+/// 所有 registry 都失败时的错误：合并各 registry 的说明，分类与上下文取第一个 registry 的
+fn all_registries_failed(package_name: &str, errors: Vec<AppError>) -> AppError {
+    let mut errors = errors.into_iter();
+    let Some(first) = errors.next() else {
+        return AppError::invalid_input(format!("没有可用于包 {} 的 registry", package_name))
+            .with_package(package_name);
+    };
+    let message = std::iter::once(first.message.clone())
+        .chain(errors.map(|e| e.message))
+        .collect::<Vec<_>>()
+        .join("；");
+    AppError { message, ..first }.with_package(package_name)
+}
 
-    // 获取 latest 版本的 tarball URL
-    let latest_version = detail
-        .dist_tags
-        .as_ref()
-        .and_then(|tags| tags.get("latest"))
-        .ok_or_else(|| format!("包 {} 没有 latest 版本", package_name))?;
+/// 条件请求刷新单个包，未变化时复用上次的结果
+async fn refresh_from_registry(
+    plugins_dir: &std::path::Path,
+    client: &RegistryClient,
+    registry: &str,
+    package_name: &str,
+    channel: &str,
+    known: Option<&PackageCacheEntry>,
+) -> Result<PackageCacheEntry, AppError> {
+    let (detail, etag, last_modified) =
+        match fetch_package_detail_if_modified(client, registry, package_name, known).await? {
+            DetailResponse::NotModified => {
+                if let Some(known) = known {
+                    return Ok(known.clone());
+                }
+                return Err(AppError::new(
+                    ErrorCode::Http,
+                    format!("包 {} 返回 304 但没有本地缓存", package_name),
+                )
+                .with_status(304)
+                .with_package(package_name));
+            }
+            DetailResponse::Modified {
+                detail,
+                etag,
+                last_modified,
+            } => (detail, etag, last_modified),
+        };
 
-    let version_detail = detail
-        .versions
-        .as_ref()
-        .and_then(|v| v.get(latest_version))
-        .ok_or_else(|| format!("找不到版本 {} 的详情", latest_version))?;
+    let version = resolve_channel_version(&detail, channel)?;
+
+    // 包详情变化了但渠道版本没变（如发布了其他渠道的版本），无需重新下载
+    let parsed = match known {
+        Some(known) if known.version == version => ParsedPlugins {
+            plugins: known.plugins.clone(),
+            warnings: known.warnings.clone(),
+        },
+        _ => {
+            let dist = resolve_version_dist(&detail, version)?;
+            let tarball_bytes = fetch_tarball(plugins_dir, client, dist, None).await?;
+            parse_tarball_plugins(plugins_dir, &tarball_bytes, package_name, Some(registry)).await?
+        }
+    };
 
-    let tarball_url = version_detail
-        .dist
-        .as_ref()
-        .and_then(|d| d.tarball.as_ref())
-        .ok_or_else(|| format!("找不到 tarball URL"))?;
+    Ok(PackageCacheEntry {
+        registry: registry.to_string(),
+        channel: channel.to_string(),
+        version: version.to_string(),
+        etag,
+        last_modified,
+        plugins: parsed.plugins,
+        warnings: parsed.warnings,
+    })
+}
 
-    // 下载 tarball
-    let tarball_resp = client
-        .get(tarball_url)
-        .send()
+/// 从单个 npm 包中提取所有插件元数据
+async fn fetch_package_plugins(
+    plugins_dir: &std::path::Path,
+    client: &RegistryClient,
+    registries: &[String],
+    package_name: &str,
+    channel: &str,
+) -> Result<Vec<PluginMeta>, AppError> {
+    refresh_package_plugins(plugins_dir, client, registries, package_name, channel, None)
         .await
-        .map_err(|e| format!("下载 tarball 失败: {e}"))?;
+        .map(|entry| entry.plugins)
+}
 
-    let tarball_bytes = tarball_resp
-        .bytes()
-        .await
-        .map_err(|e| format!("读取 tarball 失败: {e}"))?;
-
-    // 解压 .tgz 并提取 plugin.json
-    let plugin_json_content = extract_file_from_tarball(&tarball_bytes, "package/plugin.json")
-        .ok_or_else(|| format!("包 {} 中未找到 plugin.json", package_name))?;
-
-    let plugin_json: PluginJson = serde_json::from_str(&plugin_json_content)
-        .map_err(|e| format!("解析 plugin.json 失败: {e}"))?;
-
-    let entries = plugin_json.into_entries();
-    let mut plugins = Vec::new();
-
-    for entry in entries {
-        plugins.push(PluginMeta {
-            id: entry.id.clone(),
-            version: entry.version.clone(),
-            author: entry.author.clone(),
-            homepage: entry.homepage.clone(),
-            icon: entry.icon,
-            title: entry.title,
-            subtitle: entry.subtitle,
-            description: entry.description,
-            bg_color: entry.bg_color,
-            text_color: entry.text_color,
-            categories: entry.categories,
-            requires: entry.requires,
-            package_name: package_name.to_string(),
-            bundle_file: entry.bundle.clone(),
-            downloads: None,
-            rating: None,
-            updated_at: None,
-            created_at: None,
-        });
-    }
+/// 解压 .tgz 并从 plugin.json 中解析所有插件元数据
+async fn parse_tarball_plugins(
+    plugins_dir: &std::path::Path,
+    tgz_bytes: &[u8],
+    package_name: &str,
+    registry: Option<&str>,
+) -> Result<ParsedPlugins, AppError> {
+    let plugin_json_content = extract_file_from_tarball(tgz_bytes, "package/plugin.json")?
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorCode::InvalidPackage,
+                format!("包 {} 中未找到 plugin.json", package_name),
+            )
+            .with_package(package_name)
+        })?;
+
+    let installed = installed_packages(plugins_dir).await?;
+    parse_plugin_json(&plugin_json_content, package_name, registry, &installed)
+}
 
-    Ok(plugins)
+/// 从 plugin.json 解析出的插件，以及不影响使用的警告
+struct ParsedPlugins {
+    plugins: Vec<PluginMeta>,
+    warnings: Vec<manifest::Diagnostic>,
 }
 
-/// 从 .tgz (gzip + tar) 中提取指定文件内容
-fn extract_file_from_tarball(tgz_bytes: &[u8], target_path: &str) -> Option<String> {
-    use std::io::Read;
+/// 校验并解析 plugin.json，有错误时返回所有错误及其 JSON 路径（见 context.diagnostics）
+fn parse_plugin_json(
+    content: &str,
+    package_name: &str,
+    registry: Option<&str>,
+    installed: &std::collections::HashMap<String, String>,
+) -> Result<ParsedPlugins, AppError> {
+    let report = manifest::validate(content, package_name, installed);
+    let warnings = report.warnings();
+
+    let plugins = report
+        .into_result()
+        .map_err(|e| e.with_package(package_name))?
+        .into_iter()
+        .map(|entry| PluginMeta {
+            registry: registry.map(|r| r.to_string()),
+            ..entry.into_meta(package_name)
+        })
+        .collect();
+    Ok(ParsedPlugins { plugins, warnings })
+}
 
-    let gz = flate2::read::GzDecoder::new(tgz_bytes);
-    let mut archive = tar::Archive::new(gz);
+/// 已安装插件 id → 所属包名（取自索引，用于检查不同包之间的 id 冲突）
+async fn installed_packages(
+    plugins_dir: &std::path::Path,
+) -> Result<std::collections::HashMap<String, String>, AppError> {
+    Ok(load_index(plugins_dir)
+        .await?
+        .plugins
+        .into_iter()
+        .map(|(id, info)| (id, info.meta.package_name))
+        .collect())
+}
 
-    for entry in archive.entries().ok()? {
-        let mut entry = entry.ok()?;
-        let path = entry.path().ok()?;
+/// 获取 npm 包详情（GET /<package>）
+async fn fetch_package_detail(
+    client: &RegistryClient,
+    registry: &str,
+    package_name: &str,
+) -> Result<NpmPackageDetail, AppError> {
+    match fetch_package_detail_if_modified(client, registry, package_name, None).await? {
+        DetailResponse::Modified { detail, .. } => Ok(*detail),
+        DetailResponse::NotModified => Err(AppError::new(
+            ErrorCode::Http,
+            format!("包 {} 返回 304 但没有本地缓存", package_name),
+        )
+        .with_status(304)
+        .with_package(package_name)),
+    }
+}
 
-        if path.to_string_lossy() == target_path {
-            let mut content = String::new();
-            entry.read_to_string(&mut content).ok()?;
-            return Some(content);
+/// 按优先级依次尝试各 registry 获取包详情，返回成功的 registry 与详情
+async fn fetch_package_detail_from(
+    client: &RegistryClient,
+    registries: &[String],
+    package_name: &str,
+) -> Result<(String, NpmPackageDetail), AppError> {
+    let mut errors = Vec::new();
+    for registry in registries {
+        match fetch_package_detail(client, registry, package_name).await {
+            Ok(detail) => return Ok((registry.clone(), detail)),
+            Err(e) => errors.push(e.prefixed(registry)),
         }
     }
-
-    None
+    Err(all_registries_failed(package_name, errors))
 }
 
-/// 从 .tgz 中提取指定文件的原始字节
-fn extract_bytes_from_tarball(tgz_bytes: &[u8], target_path: &str) -> Option<Vec<u8>> {
-    use std::io::Read;
-
-    let gz = flate2::read::GzDecoder::new(tgz_bytes);
-    let mut archive = tar::Archive::new(gz);
-
-    for entry in archive.entries().ok()? {
-        let mut entry = entry.ok()?;
-        let path = entry.path().ok()?;
+/// 条件请求包详情的结果
+enum DetailResponse {
+    NotModified,
+    Modified {
+        detail: Box<NpmPackageDetail>,
+        etag: Option<String>,
+        last_modified: Option<String>,
+    },
+}
 
-        if path.to_string_lossy() == target_path {
-            let mut buf = Vec::new();
-            entry.read_to_end(&mut buf).ok()?;
-            return Some(buf);
+/// 带 If-None-Match / If-Modified-Since 获取包详情
+async fn fetch_package_detail_if_modified(
+    client: &RegistryClient,
+    registry: &str,
+    package_name: &str,
+    known: Option<&PackageCacheEntry>,
+) -> Result<DetailResponse, AppError> {
+    let detail_url = format!("{}/{}", registry, package_name);
+    let mut request = client.get(&detail_url);
+    if let Some(known) = known {
+        if let Some(etag) = &known.etag {
+            request = request.header(reqwest::header::IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &known.last_modified {
+            request = request.header(reqwest::header::IF_MODIFIED_SINCE, last_modified);
         }
     }
 
-    None
-}
-
-// ── 手动添加包 command ──────────────────────────────────────
+    let resp = client.send(request).await.map_err(|e| {
+        AppError::network(format!("获取包详情失败: {e}"))
+            .with_package(package_name)
+            .with_url(&detail_url)
+    })?;
 
-#[tauri::command]
-pub async fn fetch_package_by_name(
-    app: tauri::AppHandle,
-    package_name: String,
-) -> Result<Vec<PluginMeta>, String> {
-    let name = package_name.trim().to_string();
-
-    // 校验包名格式
-    if !is_usefultools_package(&name) {
-        return Err(format!(
-            "包名 \"{}\" 不符合规则，必须以 usefultools-plugin 开头（scoped 包取 / 后面的部分）",
-            name
-        ));
+    if resp.status() == reqwest::StatusCode::NOT_MODIFIED {
+        return Ok(DetailResponse::NotModified);
+    }
+    if !resp.status().is_success() {
+        let status = resp.status().as_u16();
+        let message = match status {
+            404 => format!("包 {} 不存在", package_name),
+            _ => format!("获取包 {} 的详情失败: HTTP {}", package_name, status),
+        };
+        return Err(AppError::http(status, message)
+            .with_package(package_name)
+            .with_url(&detail_url));
     }
 
-    let config = load_config(&app).await;
-    let client = reqwest::Client::new();
-
-    fetch_package_plugins(&client, &config.registry, &name).await
+    let header = |name: reqwest::header::HeaderName| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.to_string())
+    };
+    let etag = header(reqwest::header::ETAG);
+    let last_modified = header(reqwest::header::LAST_MODIFIED);
+
+    let detail = resp.json().await.map_err(|e| {
+        AppError::parse(format!("解析包详情失败: {e}"))
+            .with_package(package_name)
+            .with_url(&detail_url)
+    })?;
+
+    Ok(DetailResponse::Modified {
+        detail,
+        etag,
+        last_modified,
+    })
 }
 
-// ── 注册表获取 command ──────────────────────────────────────
-
-#[tauri::command]
-pub async fn fetch_plugin_registry(
-    app: tauri::AppHandle,
-    force_refresh: bool,
-) -> Result<Vec<PluginMeta>, String> {
-    let dir = plugins_dir(&app)?;
-    tokio::fs::create_dir_all(&dir)
-        .await
-        .map_err(|e| format!("无法创建插件目录: {e}"))?;
-
-    let cache_path = dir.join("registry-cache.json");
+/// 解析渠道（dist-tag）指向的版本，渠道不存在时回退到 latest
+fn resolve_channel_version<'a>(
+    detail: &'a NpmPackageDetail,
+    channel: &str,
+) -> Result<&'a str, AppError> {
+    let tags = detail.dist_tags.as_ref().ok_or_else(|| {
+        AppError::new(
+            ErrorCode::InvalidPackage,
+            format!("包 {} 没有 dist-tags", detail.name),
+        )
+        .with_package(&detail.name)
+    })?;
+
+    tags.get(channel)
+        .or_else(|| tags.get(DEFAULT_CHANNEL))
+        .map(|v| v.as_str())
+        .ok_or_else(|| {
+            AppError::not_found(format!("包 {} 没有 {} 版本", detail.name, channel))
+                .with_package(&detail.name)
+        })
+}
 
-    // 尝试读取本地缓存
-    let local_cache = read_cache(&cache_path).await;
+/// 取出指定版本的 dist 信息
+fn resolve_version_dist<'a>(
+    detail: &'a NpmPackageDetail,
+    version: &str,
+) -> Result<&'a NpmVersionDist, AppError> {
+    detail
+        .versions
+        .as_ref()
+        .and_then(|v| v.get(version))
+        .ok_or_else(|| {
+            AppError::not_found(format!("找不到版本 {} 的详情", version)).with_package(&detail.name)
+        })?
+        .dist
+        .as_ref()
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorCode::InvalidPackage,
+                format!("版本 {} 缺少 dist 信息", version),
+            )
+            .with_package(&detail.name)
+        })
+}
 
-    if !force_refresh {
-        if let Some(ref cache) = local_cache {
-            if cache.fetched_at + cache.ttl > now_ms() {
-                return Ok(cache.plugins.clone());
+/// 获取已校验的 tarball：优先读取本地缓存，未命中时下载并写入缓存
+///
+/// 缓存以 integrity 哈希为键，命中后仍会重新校验，损坏的缓存文件会被删除并重新下载。
+/// 写入缓存失败不影响本次结果：安装时作为警告随进度事件报告；没有安装任务时
+/// （刷新注册表、导出锁文件）不报告，之后安装该版本时会重新下载并再次写入。
+async fn fetch_tarball(
+    plugins_dir: &std::path::Path,
+    client: &RegistryClient,
+    dist: &NpmVersionDist,
+    task: Option<&progress::InstallTask>,
+) -> Result<Vec<u8>, AppError> {
+    let key = integrity::content_key(dist.integrity.as_deref(), dist.shasum.as_deref());
+
+    if let Some(key) = &key {
+        if let Some(bytes) = cache::read(plugins_dir, key).await {
+            let verified = integrity::verify_tarball(
+                &bytes,
+                dist.integrity.as_deref(),
+                dist.shasum.as_deref(),
+            );
+            if verified.is_ok() {
+                if let Some(task) = task {
+                    task.phase(progress::InstallPhase::Download)?;
+                    let len = bytes.len() as u64;
+                    task.emit(progress::InstallPhase::Download, len, Some(len));
+                }
+                return Ok(bytes);
             }
+            cache::remove(plugins_dir, key).await;
         }
     }
 
-    // 从 npm 拉取
-    let config = load_config(&app).await;
-    match fetch_npm_plugins(&config.registry).await {
-        Ok(plugins) => {
-            let new_cache = RegistryCache {
-                fetched_at: now_ms(),
-                ttl: DEFAULT_TTL,
-                plugins: plugins.clone(),
-            };
-            let _ = write_cache(&cache_path, &new_cache).await;
-            Ok(plugins)
-        }
-        Err(net_err) => {
-            if let Some(cache) = local_cache {
-                Ok(cache.plugins)
-            } else {
-                Err(format!("无法获取插件注册表且无本地缓存: {net_err}"))
-            }
+    let bytes = download_verified_tarball(client, dist, task).await?;
+    if let Some(key) = &key {
+        // 缓存只是加速下次安装，写入失败（磁盘满、权限不足）时照常安装，下次重新下载即可
+        if let (Err(e), Some(task)) = (cache::write(plugins_dir, key, &bytes).await, task) {
+            task.warn(
+                progress::InstallPhase::Download,
+                e.prefixed("tarball 未写入缓存"),
+            );
         }
     }
+    Ok(bytes)
 }
 
-async fn read_cache(path: &PathBuf) -> Option<RegistryCache> {
-    let content = tokio::fs::read_to_string(path).await.ok()?;
-    serde_json::from_str(&content).ok()
-}
+/// 下载 tarball 并按 dist.integrity / dist.shasum 校验，校验失败不返回任何数据
+///
+/// 传入安装任务时以流式读取，发送下载进度并可随时取消。
+async fn download_verified_tarball(
+    client: &RegistryClient,
+    dist: &NpmVersionDist,
+    task: Option<&progress::InstallTask>,
+) -> Result<Vec<u8>, AppError> {
+    let tarball_url = dist
+        .tarball
+        .as_ref()
+        .ok_or_else(|| AppError::new(ErrorCode::InvalidPackage, "找不到 tarball URL"))?;
 
-async fn write_cache(path: &PathBuf, cache: &RegistryCache) -> Result<(), String> {
-    let json =
-        serde_json::to_string_pretty(cache).map_err(|e| format!("序列化缓存失败: {e}"))?;
-    tokio::fs::write(path, json)
+    let mut tarball_resp = client
+        .send(client.get(tarball_url))
         .await
-        .map_err(|e| format!("写入缓存文件失败: {e}"))
-}
-
-// ── 插件安装 command ────────────────────────────────────────
-
-#[tauri::command]
-pub async fn install_plugin(
-    app: tauri::AppHandle,
-    plugin: PluginMeta,
-) -> Result<InstalledPluginInfo, String> {
-    let client = reqwest::Client::new();
-    let dir = plugins_dir(&app)?;
-    let plugin_dir = dir.join(&plugin.id);
+        .map_err(|e| AppError::network(format!("下载 tarball 失败: {e}")).with_url(tarball_url))?;
+
+    if !tarball_resp.status().is_success() {
+        let status = tarball_resp.status().as_u16();
+        return Err(
+            AppError::http(status, format!("下载 tarball 失败: HTTP {status}"))
+                .with_url(tarball_url),
+        );
+    }
 
-    tokio::fs::create_dir_all(&plugin_dir)
+    let tarball_bytes = read_tarball_body(&mut tarball_resp, task)
         .await
-        .map_err(|e| format!("无法创建插件目录: {e}"))?;
-
-    // 获取 npm 包的 tarball URL
-    let config = load_config(&app).await;
-    let detail_url = format!("{}/{}", config.registry, plugin.package_name);
-    let detail_resp = client
-        .get(&detail_url)
-        .send()
-        .await
-        .map_err(|e| format!("获取包详情失败: {e}"))?;
+        .map_err(|e| e.with_url(tarball_url))?;
 
-    let detail: NpmPackageDetail = detail_resp
-        .json()
-        .await
-        .map_err(|e| format!("解析包详情失败: {e}"))?;
+    if let Some(task) = task {
+        task.phase(progress::InstallPhase::Verify)?;
+    }
 
-    let latest_version = detail
-        .dist_tags
-        .as_ref()
-        .and_then(|tags| tags.get("latest"))
-        .ok_or_else(|| "找不到 latest 版本".to_string())?
-        .clone();
+    integrity::verify_tarball(
+        &tarball_bytes,
+        dist.integrity.as_deref(),
+        dist.shasum.as_deref(),
+    )
+    .map_err(|e| AppError::from(e).with_url(tarball_url))?;
 
-    let tarball_url = detail
+    Ok(tarball_bytes)
+}
+
+/// 流式读取 tarball 响应体，超过 tarball::MAX_TARBALL_SIZE 时中止
+///
+/// 有安装任务时发送下载进度并响应取消。
+async fn read_tarball_body(
+    resp: &mut reqwest::Response,
+    task: Option<&progress::InstallTask>,
+) -> Result<Vec<u8>, AppError> {
+    // 声明的大小已超过上限时不开始下载
+    if let Some(length) = resp.content_length() {
+        tarball::check_compressed_size(length)?;
+    }
+    if let Some(task) = task {
+        task.phase(progress::InstallPhase::Download)?;
+    }
+
+    let mut progress =
+        task.map(|task| progress::DownloadProgress::new(task, resp.content_length()));
+    let mut body = Vec::new();
+
+    loop {
+        let chunk = match task {
+            Some(task) => tokio::select! {
+                _ = task.token().cancelled() => return Err(task.cancelled_error()),
+                chunk = resp.chunk() => chunk,
+            },
+            None => resp.chunk().await,
+        };
+        let chunk = chunk.map_err(|e| AppError::network(format!("读取 tarball 失败: {e}")))?;
+        let Some(chunk) = chunk else {
+            break;
+        };
+        body.extend_from_slice(&chunk);
+        // Content-Length 缺失或与实际不符时，按已收到的字节数中止
+        tarball::check_compressed_size(body.len() as u64)?;
+        if let Some(progress) = &mut progress {
+            progress.advance(chunk.len() as u64);
+        }
+    }
+
+    if let Some(progress) = &mut progress {
+        progress.flush();
+    }
+    Ok(body)
+}
+
+/// 从 .tgz (gzip + tar) 中提取指定文件内容
+fn extract_file_from_tarball(
+    tgz_bytes: &[u8],
+    target_path: &str,
+) -> Result<Option<String>, AppError> {
+    match extract_bytes_from_tarball(tgz_bytes, target_path)? {
+        Some(bytes) => String::from_utf8(bytes).map(Some).map_err(|_| {
+            AppError::new(
+                ErrorCode::InvalidPackage,
+                format!("{} 不是有效的 UTF-8 文本", target_path),
+            )
+        }),
+        None => Ok(None),
+    }
+}
+
+/// 从 .tgz 中提取指定文件的原始字节（带大小、数量、链接与路径检查）
+fn extract_bytes_from_tarball(
+    tgz_bytes: &[u8],
+    target_path: &str,
+) -> Result<Option<Vec<u8>>, AppError> {
+    Ok(tarball::extract_file(tgz_bytes, target_path)?)
+}
+
+// ── 手动添加包 command ──────────────────────────────────────
+
+impl PluginStore {
+    pub async fn fetch_package_by_name(
+        &self,
+        package_name: String,
+    ) -> Result<Vec<PluginMeta>, AppError> {
+        let name = package_name.trim().to_string();
+
+        // 校验包名格式
+        if !is_usefultools_package(&name) {
+            return Err(AppError::invalid_input(format!(
+                "包名 \"{}\" 不符合规则，必须以 usefultools-plugin 开头（scoped 包取 / 后面的部分）",
+                name
+            ))
+            .with_package(name));
+        }
+
+        let config = load_config(self).await;
+        let result = async {
+            let client = registry_client(self, &config).await?;
+            let dir = self.plugins_dir();
+            let channel = config.channel_for(&name);
+            let registries = config.registries_for(&name);
+            fetch_package_plugins(dir, &client, &registries, &name, channel).await
+        }
+        .await;
+        redact_err(self, result).await
+    }
+}
+
+#[tauri::command]
+pub async fn fetch_package_by_name(
+    app: tauri::AppHandle,
+    package_name: String,
+) -> Result<Vec<PluginMeta>, AppError> {
+    PluginStore::from_app(&app)?
+        .fetch_package_by_name(package_name)
+        .await
+}
+
+// ── 注册表获取 command ──────────────────────────────────────
+
+impl PluginStore {
+    /// 获取插件注册表（缓存未过期且不强制刷新时直接使用缓存）
+    pub async fn fetch_registry(
+        &self,
+        force_refresh: bool,
+    ) -> Result<RegistryFetchResult, AppError> {
+        let dir = self.plugins_dir();
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| AppError::io(format!("无法创建插件目录: {e}")))?;
+
+        let cache_path = dir.join("registry-cache.json");
+
+        // 尝试读取本地缓存
+        let local_cache = read_cache(&cache_path).await;
+
+        if !force_refresh {
+            if let Some(ref cache) = local_cache {
+                if cache.fetched_at + cache.ttl > now_ms() {
+                    return Ok(RegistryFetchResult {
+                        plugins: cache.plugins.clone(),
+                        errors: cache.errors.clone(),
+                        warnings: package_warnings(&cache.packages),
+                    });
+                }
+            }
+        }
+
+        // 从 npm 拉取，未变化的包通过条件请求跳过
+        let config = load_config(self).await;
+        let crawl = match registry_client(self, &config).await {
+            Ok(client) => fetch_npm_plugins(dir, &client, &config, local_cache.as_ref()).await,
+            Err(e) => Err(e),
+        };
+        match crawl {
+            Ok(crawl) => {
+                let warnings = package_warnings(&crawl.packages);
+                let new_cache = RegistryCache {
+                    fetched_at: now_ms(),
+                    ttl: DEFAULT_TTL,
+                    plugins: crawl.plugins.clone(),
+                    packages: crawl.packages,
+                    errors: crawl.errors.clone(),
+                };
+                let _ = write_cache(&cache_path, &new_cache).await;
+                Ok(RegistryFetchResult {
+                    plugins: crawl.plugins,
+                    errors: crawl.errors,
+                    warnings,
+                })
+            }
+            Err(net_err) => {
+                if let Some(cache) = local_cache {
+                    Ok(RegistryFetchResult {
+                        warnings: package_warnings(&cache.packages),
+                        plugins: cache.plugins,
+                        errors: cache.errors,
+                    })
+                } else {
+                    let error = net_err.prefixed("无法获取插件注册表且无本地缓存");
+                    redact_err(self, Err(error)).await
+                }
+            }
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn fetch_plugin_registry(
+    app: tauri::AppHandle,
+    force_refresh: bool,
+) -> Result<RegistryFetchResult, AppError> {
+    PluginStore::from_app(&app)?
+        .fetch_registry(force_refresh)
+        .await
+}
+
+/// 读取注册表缓存；无法解析时（包括旧版本以纯文本保存错误的缓存）视为没有缓存
+async fn read_cache(path: &PathBuf) -> Option<RegistryCache> {
+    let content = tokio::fs::read_to_string(path).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// 将缓存标记为过期（保留内容作为离线回退）
+async fn expire_cache(path: &PathBuf) {
+    if let Some(mut cache) = read_cache(path).await {
+        cache.fetched_at = 0;
+        let _ = write_cache(path, &cache).await;
+    }
+}
+
+async fn write_cache(path: &PathBuf, cache: &RegistryCache) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(cache)
+        .map_err(|e| AppError::internal(format!("序列化缓存失败: {e}")))?;
+    tokio::fs::write(path, json)
+        .await
+        .map_err(|e| AppError::io(format!("写入缓存文件失败: {e}")))
+}
+
+// ── 插件安装 command ────────────────────────────────────────
+
+impl PluginStore {
+    /// 从 registry 安装插件，先安装缺失的依赖
+    pub async fn install_plugin(
+        &self,
+        plugin: PluginMeta,
+    ) -> Result<InstalledPluginInfo, AppError> {
+        let guard = self.tasks().register(&plugin.id)?;
+        let task = guard.task(self);
+        let result = async {
+            task.phase(progress::InstallPhase::Resolve)?;
+
+            // 先安装缺失的依赖插件
+            for dep in resolve_dependencies(self, &plugin).await? {
+                install_from_registry(self, &dep, None, &task.for_plugin(&dep.id)).await?;
+            }
+            install_from_registry(self, &plugin, None, &task).await
+        }
+        .await;
+        redact_err(self, result).await
+    }
+}
+
+#[tauri::command]
+pub async fn install_plugin(
+    app: tauri::AppHandle,
+    plugin: PluginMeta,
+) -> Result<InstalledPluginInfo, AppError> {
+    PluginStore::from_app(&app)?.install_plugin(plugin).await
+}
+
+/// 解析插件依赖，返回需要先安装的插件（按安装顺序，已安装的依赖除外）
+async fn resolve_dependencies(
+    store: &PluginStore,
+    plugin: &PluginMeta,
+) -> Result<Vec<PluginMeta>, AppError> {
+    if plugin.dependencies.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let installed = store.installed_plugins().await?;
+    let registry = store.fetch_registry(false).await?.plugins;
+
+    let mut deps_of: std::collections::HashMap<String, Vec<String>> = registry
+        .iter()
+        .chain(installed.iter().map(|p| &p.meta))
+        .map(|p| (p.id.clone(), p.dependencies.clone()))
+        .collect();
+    deps_of.insert(plugin.id.clone(), plugin.dependencies.clone());
+
+    let installed_ids: std::collections::HashSet<String> =
+        installed.iter().map(|p| p.meta.id.clone()).collect();
+
+    let order = dependency::resolve_install_order(&plugin.id, &deps_of, &installed_ids)
+        .map_err(|e| AppError::from(e).with_plugin(&plugin.id))?;
+
+    Ok(order
+        .into_iter()
+        .filter_map(|id| registry.iter().find(|p| p.id == id).cloned())
+        .collect())
+}
+
+/// 下载并安装插件；未指定版本时安装所选渠道的版本
+///
+/// 元数据取自该版本 tarball 中的 plugin.json，保证 bundle 路径与版本号一致。
+async fn install_from_registry(
+    store: &PluginStore,
+    plugin: &PluginMeta,
+    version: Option<&str>,
+    task: &progress::InstallTask,
+) -> Result<InstalledPluginInfo, AppError> {
+    let result = async {
+        let package = download_package(
+            store,
+            &plugin.package_name,
+            plugin.registry.as_deref(),
+            version,
+            task,
+        )
+        .await?;
+        install_from_package(store, &package, &plugin.id, task).await
+    }
+    .await;
+    result.map_err(|e| e.with_plugin(&plugin.id))
+}
+
+/// 已下载并校验的包
+struct PackageTarball {
+    version: String,
+    bytes: Vec<u8>,
+    plugins: Vec<PluginMeta>,
+}
+
+/// 解析版本、获取 tarball（优先缓存）并解析其中的 plugin.json
+///
+/// 指定了 registry（插件来自的 registry）时只使用它，否则按配置的优先级回退。
+async fn download_package(
+    store: &PluginStore,
+    package_name: &str,
+    registry: Option<&str>,
+    version: Option<&str>,
+    task: &progress::InstallTask,
+) -> Result<PackageTarball, AppError> {
+    task.phase(progress::InstallPhase::Resolve)?;
+    let config = load_config(store).await;
+    let client = registry_client(store, &config).await?;
+    let dir = store.plugins_dir();
+    let registries = match registry {
+        Some(registry) => vec![registry.to_string()],
+        None => config.registries_for(package_name),
+    };
+    let (registry, detail) = fetch_package_detail_from(&client, &registries, package_name).await?;
+
+    let version = match version {
+        Some(v) => v,
+        None => resolve_channel_version(&detail, config.channel_for(package_name))?,
+    };
+    let dist = resolve_version_dist(&detail, version)?;
+    let bytes = fetch_tarball(dir, &client, dist, Some(task)).await?;
+
+    task.phase(progress::InstallPhase::Extract)?;
+    let integrity = integrity::sri(&bytes);
+    // 警告不影响安装（浏览注册表时已展示）
+    let plugins = parse_tarball_plugins(dir, &bytes, package_name, Some(&registry))
+        .await?
+        .plugins
+        .into_iter()
+        .map(|plugin| PluginMeta {
+            integrity: Some(integrity.clone()),
+            ..plugin
+        })
+        .collect();
+
+    Ok(PackageTarball {
+        version: version.to_string(),
+        bytes,
+        plugins,
+    })
+}
+
+/// 从已下载的包中安装单个插件
+async fn install_from_package(
+    store: &PluginStore,
+    package: &PackageTarball,
+    plugin_id: &str,
+    task: &progress::InstallTask,
+) -> Result<InstalledPluginInfo, AppError> {
+    let meta = package
+        .plugins
+        .iter()
+        .find(|p| p.id == plugin_id)
+        .cloned()
+        .ok_or_else(|| {
+            AppError::not_found(format!(
+                "版本 {} 中不存在插件 {}",
+                package.version, plugin_id
+            ))
+            .with_plugin(plugin_id)
+        })?;
+
+    write_plugin_files(store, meta, &package.bytes, Some(task)).await
+}
+
+// ── 批量安装 command ────────────────────────────────────────
+
+/// 批量安装中失败的插件
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchInstallFailure {
+    pub plugin_id: String,
+    pub error: AppError,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchInstallResult {
+    pub installed: Vec<InstalledPluginInfo>,
+    pub failed: Vec<BatchInstallFailure>,
+}
+
+impl PluginStore {
+    /// 一次安装多个插件，同一个包只解析和下载一次
+    ///
+    /// 缺失的依赖会先于依赖它的插件安装；单个插件失败不影响其他插件，
+    /// 依赖安装失败的插件会一并记为失败。取消其中任意一个插件即取消整批。
+    pub async fn install_plugins(
+        &self,
+        plugins: Vec<PluginMeta>,
+    ) -> Result<BatchInstallResult, AppError> {
+        let mut seen = std::collections::HashSet::new();
+        let plugins: Vec<PluginMeta> = plugins
+            .into_iter()
+            .filter(|p| seen.insert(p.id.clone()))
+            .collect();
+        if plugins.is_empty() {
+            return Ok(BatchInstallResult {
+                installed: Vec::new(),
+                failed: Vec::new(),
+            });
+        }
+
+        let ids: Vec<String> = plugins.iter().map(|p| p.id.clone()).collect();
+        let guard = self.tasks().register_many(&ids)?;
+
+        let mut failed: Vec<BatchInstallFailure> = Vec::new();
+
+        // 按安装顺序展开：每个插件缺失的依赖排在它前面
+        let mut order: Vec<(PluginMeta, String)> = Vec::new();
+        let mut queued = std::collections::HashSet::new();
+        for plugin in &plugins {
+            let task = guard.task_for(self, &plugin.id);
+            task.phase(progress::InstallPhase::Resolve)?;
+            match resolve_dependencies(self, plugin).await {
+                Ok(deps) => {
+                    for dep in deps.into_iter().chain(std::iter::once(plugin.clone())) {
+                        if queued.insert(dep.id.clone()) {
+                            order.push((dep, plugin.id.clone()));
+                        }
+                    }
+                }
+                Err(error) => failed.push(BatchInstallFailure {
+                    plugin_id: plugin.id.clone(),
+                    error,
+                }),
+            }
+        }
+
+        let mut packages: std::collections::HashMap<String, Result<PackageTarball, AppError>> =
+            std::collections::HashMap::new();
+        let mut installed = Vec::new();
+        let mut failed_ids: std::collections::HashSet<String> =
+            failed.iter().map(|f| f.plugin_id.clone()).collect();
+
+        for (plugin, task_id) in order {
+            let task = guard.task_for(self, &task_id).for_plugin(&plugin.id);
+
+            let result = match plugin
+                .dependencies
+                .iter()
+                .find(|dep| failed_ids.contains(*dep))
+            {
+                Some(dep) => Err(AppError::new(
+                    ErrorCode::Dependency,
+                    format!("依赖插件 {dep} 安装失败"),
+                )
+                .with_plugin(&plugin.id)),
+                None => {
+                    if !packages.contains_key(&plugin.package_name) {
+                        let package = download_package(
+                            self,
+                            &plugin.package_name,
+                            plugin.registry.as_deref(),
+                            None,
+                            &task,
+                        )
+                        .await;
+                        packages.insert(plugin.package_name.clone(), package);
+                    }
+                    match &packages[&plugin.package_name] {
+                        Ok(package) => install_from_package(self, package, &plugin.id, &task).await,
+                        Err(e) => Err(e.clone()),
+                    }
+                }
+            };
+
+            match result {
+                Ok(info) => installed.push(info),
+                Err(error) => {
+                    failed_ids.insert(plugin.id.clone());
+                    failed.push(BatchInstallFailure {
+                        plugin_id: plugin.id,
+                        error,
+                    });
+                }
+            }
+        }
+
+        let credentials = load_credentials(self).await;
+        for failure in &mut failed {
+            failure.error = credentials.redact_error(failure.error.clone());
+        }
+        Ok(BatchInstallResult { installed, failed })
+    }
+}
+
+#[tauri::command]
+pub async fn install_plugins(
+    app: tauri::AppHandle,
+    plugins: Vec<PluginMeta>,
+) -> Result<BatchInstallResult, AppError> {
+    PluginStore::from_app(&app)?.install_plugins(plugins).await
+}
+
+// ── 从本地文件安装 command ──────────────────────────────────
+
+/// 从本地文件安装的插件记录的来源
+pub const LOCAL_FILE_ORIGIN: &str = "local file";
+
+/// 本地的包：npm pack 生成的 .tgz 或解压后的包目录
+enum LocalPackage {
+    Tarball(Vec<u8>),
+    Directory(PathBuf),
+}
+
+impl LocalPackage {
+    async fn open(path: &std::path::Path) -> Result<Self, AppError> {
+        let file_meta = tokio::fs::metadata(path)
+            .await
+            .map_err(|e| AppError::file(format!("无法读取 {}: {e}", path.display()), &e))?;
+        if file_meta.is_dir() {
+            return Ok(LocalPackage::Directory(path.to_path_buf()));
+        }
+        tarball::check_compressed_size(file_meta.len())?;
+        let bytes = tokio::fs::read(path)
+            .await
+            .map_err(|e| AppError::file(format!("读取 {} 失败: {e}", path.display()), &e))?;
+        Ok(LocalPackage::Tarball(bytes))
+    }
+
+    /// 读取包内文件（相对包根目录），不存在时返回 None
+    async fn read(&self, relative: &str) -> Result<Option<Vec<u8>>, AppError> {
+        match self {
+            LocalPackage::Tarball(bytes) => {
+                extract_bytes_from_tarball(bytes, &format!("package/{relative}"))
+            }
+            LocalPackage::Directory(dir) => {
+                if !tarball::is_safe_relative_path(relative) {
+                    return Err(AppError::new(
+                        ErrorCode::InvalidPackage,
+                        format!("包中包含不安全的路径: {relative}"),
+                    ));
+                }
+                let path = dir.join(relative);
+                // 与 tarball 相同：不跟随链接，单个文件不超过上限
+                let file_meta = match tokio::fs::symlink_metadata(&path).await {
+                    Ok(file_meta) => file_meta,
+                    Err(_) => return Ok(None),
+                };
+                if !file_meta.is_file() {
+                    return Err(AppError::new(
+                        ErrorCode::InvalidPackage,
+                        format!("{relative} 不是普通文件"),
+                    ));
+                }
+                // 中间的目录也可能是链接，解析后必须仍在包目录内
+                let root = tokio::fs::canonicalize(dir)
+                    .await
+                    .map_err(|e| AppError::file(format!("无法读取 {}: {e}", dir.display()), &e))?;
+                let path = tokio::fs::canonicalize(&path)
+                    .await
+                    .map_err(|e| AppError::io(format!("读取 {relative} 失败: {e}")))?;
+                if !path.starts_with(&root) {
+                    return Err(AppError::new(
+                        ErrorCode::InvalidPackage,
+                        format!("{relative} 位于包目录之外"),
+                    ));
+                }
+                if file_meta.len() > tarball::MAX_ENTRY_SIZE {
+                    return Err(AppError::new(
+                        ErrorCode::InvalidPackage,
+                        format!(
+                            "文件 {relative} 大小 {} 字节，超过 {} 字节上限",
+                            file_meta.len(),
+                            tarball::MAX_ENTRY_SIZE
+                        ),
+                    ));
+                }
+                tokio::fs::read(&path)
+                    .await
+                    .map(Some)
+                    .map_err(|e| AppError::io(format!("读取 {relative} 失败: {e}")))
+            }
+        }
+    }
+
+    /// 包名取自 package.json，缺失时使用文件名
+    async fn package_name(&self, path: &std::path::Path) -> Result<String, AppError> {
+        if let Some(bytes) = self.read("package.json").await? {
+            let package_json: NpmPackageJson = serde_json::from_slice(&bytes)
+                .map_err(|e| AppError::parse(format!("解析 package.json 失败: {e}")))?;
+            return Ok(package_json.name);
+        }
+        let stem = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Ok(stem.trim_end_matches(".tgz").to_string())
+    }
+
+    /// 解析并校验 plugin.json，插件来源记为本地文件
+    async fn plugins(
+        &self,
+        package_name: &str,
+        installed: &std::collections::HashMap<String, String>,
+    ) -> Result<Vec<PluginMeta>, AppError> {
+        let content = self.read("plugin.json").await?.ok_or_else(|| {
+            AppError::new(
+                ErrorCode::InvalidPackage,
+                format!("包 {} 中未找到 plugin.json", package_name),
+            )
+            .with_package(package_name)
+        })?;
+        let content = String::from_utf8(content).map_err(|_| {
+            AppError::new(
+                ErrorCode::InvalidPackage,
+                "plugin.json 不是有效的 UTF-8 文本",
+            )
+            .with_package(package_name)
+        })?;
+
+        let integrity = self.integrity();
+        // 警告不影响安装，可通过 validate_local_plugin_json 查看
+        Ok(parse_plugin_json(&content, package_name, None, installed)?
+            .plugins
+            .into_iter()
+            .map(|plugin| PluginMeta {
+                origin: Some(LOCAL_FILE_ORIGIN.to_string()),
+                integrity: integrity.clone(),
+                ..plugin
+            })
+            .collect())
+    }
+
+    /// .tgz 的 SRI 哈希（目录没有）
+    fn integrity(&self) -> Option<String> {
+        match self {
+            LocalPackage::Tarball(bytes) => Some(integrity::sri(bytes)),
+            LocalPackage::Directory(_) => None,
+        }
+    }
+}
+
+impl PluginStore {
+    /// 从本地 .tgz（npm pack 生成）或包目录永久安装插件，无需访问 registry
+    ///
+    /// plugin_ids 为空时安装包中的全部插件。依赖的插件必须已安装或在同一个包中，
+    /// 同包中的依赖会先于依赖它的插件安装。
+    pub async fn install_plugin_from_file(
+        &self,
+        path: String,
+        plugin_ids: Option<Vec<String>>,
+    ) -> Result<Vec<InstalledPluginInfo>, AppError> {
+        let path = PathBuf::from(path);
+        let package = LocalPackage::open(&path).await?;
+        let package_name = package.package_name(&path).await?;
+        let installed = installed_packages(self.plugins_dir()).await?;
+        let plugins = package.plugins(&package_name, &installed).await?;
+
+        let requested = match plugin_ids {
+            Some(ids) if !ids.is_empty() => ids,
+            _ => plugins.iter().map(|p| p.id.clone()).collect(),
+        };
+
+        // 按安装顺序展开：同包中缺失的依赖排在依赖它的插件前面
+        let installed_ids: std::collections::HashSet<String> = self
+            .installed_plugins()
+            .await?
+            .into_iter()
+            .map(|p| p.meta.id)
+            .collect();
+        let deps_of: std::collections::HashMap<String, Vec<String>> = plugins
+            .iter()
+            .map(|p| (p.id.clone(), p.dependencies.clone()))
+            .collect();
+        let mut order: Vec<String> = Vec::new();
+        for id in &requested {
+            if !deps_of.contains_key(id) {
+                return Err(AppError::not_found(format!(
+                    "包 {} 中不存在插件 {}",
+                    package_name, id
+                ))
+                .with_plugin(id)
+                .with_package(package_name));
+            }
+            let deps = dependency::resolve_install_order(id, &deps_of, &installed_ids).map_err(
+                |e| match e {
+                    dependency::DependencyError::Missing { plugin, dependency } => AppError::new(
+                        ErrorCode::Dependency,
+                        format!("插件 {plugin} 依赖的 {dependency} 未安装，且不在该包中"),
+                    )
+                    .with_plugin(plugin),
+                    e => AppError::from(e).with_plugin(id),
+                },
+            )?;
+            for dep in deps.into_iter().chain(std::iter::once(id.clone())) {
+                if !order.contains(&dep) {
+                    order.push(dep);
+                }
+            }
+        }
+
+        let guard = self.tasks().register_many(&order)?;
+
+        // .tgz 放入缓存，导出锁文件时可以直接打包。写入失败时不影响安装，作为警告报告；
+        // 之后导出带 tarball 的锁文件会明确报告缺少该插件的 tarball
+        if let (LocalPackage::Tarball(bytes), Some(integrity)) = (&package, package.integrity()) {
+            if let Some(key) = integrity::content_key(Some(&integrity), None) {
+                let written = cache::write(self.plugins_dir(), &key, bytes).await;
+                if let (Err(e), Some(first)) = (written, order.first()) {
+                    guard.task_for(self, first).warn(
+                        progress::InstallPhase::Write,
+                        e.prefixed("tarball 未写入缓存，导出锁文件时无法附带它"),
+                    );
+                }
+            }
+        }
+        let mut result = Vec::new();
+        for id in &order {
+            let task = guard.task_for(self, id);
+            task.phase(progress::InstallPhase::Extract)?;
+
+            let plugin = plugins
+                .iter()
+                .find(|p| &p.id == id)
+                .cloned()
+                .ok_or_else(|| {
+                    AppError::not_found(format!("包 {} 中不存在插件 {}", package_name, id))
+                        .with_plugin(id)
+                        .with_package(&package_name)
+                })?;
+            let bundle_bytes = package.read(&plugin.bundle_file).await?.ok_or_else(|| {
+                AppError::new(
+                    ErrorCode::InvalidPackage,
+                    format!("包 {} 中未找到 {}", package_name, plugin.bundle_file),
+                )
+                .with_plugin(id)
+                .with_package(&package_name)
+            })?;
+
+            result.push(write_plugin_bundle(self, plugin, &bundle_bytes, Some(&task)).await?);
+        }
+        Ok(result)
+    }
+}
+
+#[tauri::command]
+pub async fn install_plugin_from_file(
+    app: tauri::AppHandle,
+    path: String,
+    plugin_ids: Option<Vec<String>>,
+) -> Result<Vec<InstalledPluginInfo>, AppError> {
+    PluginStore::from_app(&app)?
+        .install_plugin_from_file(path, plugin_ids)
+        .await
+}
+
+// ── 锁文件 command ──────────────────────────────────────────
+
+/// 锁文件所在目录，打包的 tarball 路径相对于它
+fn lockfile_base(path: &str) -> PathBuf {
+    std::path::Path::new(path)
+        .parent()
+        .map(|p| p.to_path_buf())
+        .unwrap_or_default()
+}
+
+impl PluginStore {
+    /// 导出已安装插件的锁文件
+    ///
+    /// `bundle` 为 true 时把每个插件的 tarball 写入锁文件旁的 tarballs 目录（优先取缓存，
+    /// 否则从 registry 下载），导入时无需联网。
+    pub async fn export_lockfile(
+        &self,
+        path: String,
+        bundle: Option<bool>,
+    ) -> Result<Lockfile, AppError> {
+        let result = async {
+            let installed = self.installed_plugins().await?;
+            let mut lockfile = Lockfile::from_installed(installed.iter().map(|p| &p.meta));
+
+            if bundle.unwrap_or(false) {
+                let tarball_dir = lockfile_base(&path).join(lockfile::TARBALL_DIR);
+                tokio::fs::create_dir_all(&tarball_dir)
+                    .await
+                    .map_err(|e| AppError::io(format!("无法创建 tarball 目录: {e}")))?;
+
+                for locked in &mut lockfile.plugins {
+                    let key = locked
+                        .integrity
+                        .as_deref()
+                        .and_then(|integrity| integrity::content_key(Some(integrity), None))
+                        .ok_or_else(|| {
+                            AppError::invalid_input(format!(
+                                "插件 {} 没有记录 tarball 完整性（可能从本地目录安装），无法打包",
+                                locked.id
+                            ))
+                            .with_plugin(&locked.id)
+                        })?;
+                    let file_name = format!("{key}.tgz");
+                    let target = tarball_dir.join(&file_name);
+                    // 同一个包的多个插件共用一个 tarball
+                    if !target.is_file() {
+                        let bytes = fetch_locked_tarball(self, locked, None).await?;
+                        tokio::fs::write(&target, &bytes).await.map_err(|e| {
+                            AppError::io(format!("写入 {} 失败: {e}", target.display()))
+                        })?;
+                    }
+                    locked.tarball = Some(format!("{}/{}", lockfile::TARBALL_DIR, file_name));
+                }
+            }
+
+            let content = serde_json::to_string_pretty(&lockfile)
+                .map_err(|e| AppError::internal(format!("序列化锁文件失败: {e}")))?;
+            tokio::fs::write(&path, content)
+                .await
+                .map_err(|e| AppError::io(format!("写入锁文件失败: {e}")))?;
+            Ok(lockfile)
+        }
+        .await;
+        redact_err(self, result).await
+    }
+}
+
+#[tauri::command]
+pub async fn export_plugin_lockfile(
+    app: tauri::AppHandle,
+    path: String,
+    bundle: Option<bool>,
+) -> Result<Lockfile, AppError> {
+    PluginStore::from_app(&app)?
+        .export_lockfile(path, bundle)
+        .await
+}
+
+/// 导入锁文件的结果
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LockfileImportResult {
+    pub diff: LockfileDiff,
+    /// 为 true 时只计算差异，未做任何修改
+    pub dry_run: bool,
+    pub installed: Vec<InstalledPluginInfo>,
+    pub removed: Vec<String>,
+    pub failed: Vec<BatchInstallFailure>,
+}
+
+impl PluginStore {
+    /// 按锁文件安装完全相同的插件集合：安装缺失或版本不同的插件，卸载锁文件中没有的插件
+    ///
+    /// `dry_run` 为 true 时只返回差异。单个插件安装失败不影响其他插件，
+    /// 有插件安装失败、或要卸载的插件仍被保留的插件依赖时，不会卸载任何插件。
+    pub async fn import_lockfile(
+        &self,
+        path: String,
+        dry_run: Option<bool>,
+    ) -> Result<LockfileImportResult, AppError> {
+        let content = tokio::fs::read_to_string(&path)
+            .await
+            .map_err(|e| AppError::file(format!("读取锁文件失败: {e}"), &e))?;
+        let target = Lockfile::parse(&content)?;
+
+        let installed = self.installed_plugins().await?;
+        let current = Lockfile::from_installed(installed.iter().map(|p| &p.meta));
+        let diff = lockfile::diff(&current.plugins, &target.plugins);
+
+        let mut result = LockfileImportResult {
+            diff,
+            dry_run: dry_run.unwrap_or(false),
+            installed: Vec::new(),
+            removed: Vec::new(),
+            failed: Vec::new(),
+        };
+        if result.dry_run {
+            return Ok(result);
+        }
+
+        let to_install: Vec<&lockfile::LockedPlugin> = result
+            .diff
+            .added
+            .iter()
+            .chain(result.diff.changed.iter().map(|c| &c.to))
+            .collect();
+        let ids: Vec<String> = to_install.iter().map(|p| p.id.clone()).collect();
+        let guard = self.tasks().register_many(&ids)?;
+        let base = lockfile_base(&path);
+
+        // 同一个包的同一版本只读取 / 下载一次
+        let mut packages: std::collections::HashMap<
+            (String, String),
+            Result<PackageTarball, AppError>,
+        > = std::collections::HashMap::new();
+        for locked in to_install {
+            let task = guard.task_for(self, &locked.id);
+            let key = (locked.package_name.clone(), locked.version.clone());
+            if !packages.contains_key(&key) {
+                let package = load_locked_package(self, &base, locked, &task).await;
+                packages.insert(key.clone(), package);
+            }
+            let installed = match &packages[&key] {
+                Ok(package) => install_from_package(self, package, &locked.id, &task).await,
+                Err(e) => Err(e.clone()),
+            };
+            match installed {
+                Ok(info) => result.installed.push(info),
+                Err(error) => result.failed.push(BatchInstallFailure {
+                    plugin_id: locked.id.clone(),
+                    error,
+                }),
+            }
+        }
+
+        let ids: Vec<String> = result.diff.removed.iter().map(|p| p.id.clone()).collect();
+        if result.failed.is_empty() {
+            result.failed = blocked_removals(self, &ids).await?;
+        }
+        if result.failed.is_empty() {
+            let mut removal = Ok(());
+            for id in &ids {
+                removal = remove_plugin_dir(self, id).await;
+                if removal.is_err() {
+                    break;
+                }
+                result.removed.push(id.clone());
+            }
+            let synced = sync_index(self, &ids).await;
+            removal?;
+            synced?;
+        }
+
+        let credentials = load_credentials(self).await;
+        for failure in &mut result.failed {
+            failure.error = credentials.redact_error(failure.error.clone());
+        }
+        Ok(result)
+    }
+}
+
+#[tauri::command]
+pub async fn import_plugin_lockfile(
+    app: tauri::AppHandle,
+    path: String,
+    dry_run: Option<bool>,
+) -> Result<LockfileImportResult, AppError> {
+    PluginStore::from_app(&app)?
+        .import_lockfile(path, dry_run)
+        .await
+}
+
+/// 找出仍被不在 `ids` 中的已安装插件（直接或间接）依赖、因而不能卸载的插件
+async fn blocked_removals(
+    store: &PluginStore,
+    ids: &[String],
+) -> Result<Vec<BatchInstallFailure>, AppError> {
+    let deps_of: std::collections::HashMap<String, Vec<String>> = store
+        .installed_plugins()
+        .await?
+        .into_iter()
+        .map(|p| (p.meta.id, p.meta.dependencies))
+        .collect();
+
+    Ok(ids
+        .iter()
+        .filter_map(|id| {
+            let dependents: Vec<String> = dependency::collect_dependents(id, &deps_of)
+                .into_iter()
+                .filter(|dependent| !ids.contains(dependent))
+                .collect();
+            (!dependents.is_empty()).then(|| BatchInstallFailure {
+                plugin_id: id.clone(),
+                error: AppError::new(
+                    ErrorCode::Dependency,
+                    format!("插件 {} 仍被以下插件依赖: {}", id, dependents.join(", ")),
+                )
+                .with_plugin(id),
+            })
+        })
+        .collect())
+}
+
+/// 取得锁定插件的包：优先使用打包的 tarball，否则从缓存或 registry 获取
+async fn load_locked_package(
+    store: &PluginStore,
+    base: &std::path::Path,
+    locked: &lockfile::LockedPlugin,
+    task: &progress::InstallTask,
+) -> Result<PackageTarball, AppError> {
+    task.phase(progress::InstallPhase::Resolve)?;
+    let bytes = match &locked.tarball {
+        Some(tarball) => {
+            let path = base.join(tarball);
+            let bytes = tokio::fs::read(&path).await.map_err(|e| {
+                AppError::file(
+                    format!("读取打包的 tarball {} 失败: {e}", path.display()),
+                    &e,
+                )
+            })?;
+            task.phase(progress::InstallPhase::Verify)?;
+            if let Some(expected) = &locked.integrity {
+                integrity::verify_sri(&bytes, expected)?;
+            }
+            bytes
+        }
+        None => fetch_locked_tarball(store, locked, Some(task)).await?,
+    };
+
+    task.phase(progress::InstallPhase::Extract)?;
+    let integrity = integrity::sri(&bytes);
+    // 警告不影响按锁文件安装
+    let plugins = parse_tarball_plugins(
+        store.plugins_dir(),
+        &bytes,
+        &locked.package_name,
+        locked.registry.as_deref(),
+    )
+    .await?
+    .plugins
+    .into_iter()
+    .map(|plugin| PluginMeta {
+        origin: locked.origin.clone(),
+        integrity: Some(integrity.clone()),
+        ..plugin
+    })
+    .collect();
+
+    Ok(PackageTarball {
+        version: locked.version.clone(),
+        bytes,
+        plugins,
+    })
+}
+
+/// 获取锁定版本的 tarball（先查缓存，再从记录的 registry 下载），并校验锁定的 integrity
+async fn fetch_locked_tarball(
+    store: &PluginStore,
+    locked: &lockfile::LockedPlugin,
+    task: Option<&progress::InstallTask>,
+) -> Result<Vec<u8>, AppError> {
+    let dir = store.plugins_dir();
+
+    if let Some(expected) = &locked.integrity {
+        if let Some(key) = integrity::content_key(Some(expected), None) {
+            if let Some(bytes) = cache::read(dir, &key).await {
+                if integrity::verify_sri(&bytes, expected).is_ok() {
+                    return Ok(bytes);
+                }
+                cache::remove(dir, &key).await;
+            }
+        }
+    }
+
+    if locked.origin.is_some() {
+        return Err(AppError::not_found(format!(
+            "插件 {} 从本地文件安装，缓存中没有它的 tarball，请在导出锁文件时打包 tarball",
+            locked.id
+        ))
+        .with_plugin(&locked.id));
+    }
+
+    let config = load_config(store).await;
+    let client = registry_client(store, &config).await?;
+    let registries = match &locked.registry {
+        Some(registry) => vec![registry.clone()],
+        None => config.registries_for(&locked.package_name),
+    };
+    let (_, detail) = fetch_package_detail_from(&client, &registries, &locked.package_name).await?;
+    let dist = resolve_version_dist(&detail, &locked.version)?;
+    let bytes = fetch_tarball(dir, &client, dist, task).await?;
+
+    if let Some(expected) = &locked.integrity {
+        integrity::verify_sri(&bytes, expected)?;
+    }
+    Ok(bytes)
+}
+
+// ── tarball 缓存 command ────────────────────────────────────
+
+impl PluginStore {
+    /// 清空 tarball 缓存，返回释放的字节数
+    pub async fn clear_cache(&self) -> Result<u64, AppError> {
+        let dir = self.plugins_dir();
+        cache::clear(dir).await
+    }
+}
+
+#[tauri::command]
+pub async fn clear_plugin_cache(app: tauri::AppHandle) -> Result<u64, AppError> {
+    PluginStore::from_app(&app)?.clear_cache().await
+}
+
+/// 更新时需要保留的插件状态文件
+const STATE_FILES: &[&str] = &["pin.json", "disabled.json"];
+
+/// 从已校验的 tarball 中提取 bundle 并写入插件目录
+async fn write_plugin_files(
+    store: &PluginStore,
+    plugin: PluginMeta,
+    tarball_bytes: &[u8],
+    task: Option<&progress::InstallTask>,
+) -> Result<InstalledPluginInfo, AppError> {
+    if let Some(task) = task {
+        task.phase(progress::InstallPhase::Extract)?;
+    }
+
+    // 从 tarball 中提取 bundle 文件
+    let bundle_tar_path = format!("package/{}", plugin.bundle_file);
+    let bundle_bytes =
+        extract_bytes_from_tarball(tarball_bytes, &bundle_tar_path)?.ok_or_else(|| {
+            AppError::new(
+                ErrorCode::InvalidPackage,
+                format!("tarball 中未找到 {}", bundle_tar_path),
+            )
+            .with_plugin(&plugin.id)
+        })?;
+
+    write_plugin_bundle(store, plugin, &bundle_bytes, task).await
+}
+
+/// 将 bundle 与元数据写入插件目录
+///
+/// 新版本先完整写入暂存目录再原子换入；若插件已安装，当前的 bundle.mjs / meta.json
+/// 会作为 .bak 备份一并保留，供回滚使用。
+async fn write_plugin_bundle(
+    store: &PluginStore,
+    plugin: PluginMeta,
+    bundle_bytes: &[u8],
+    task: Option<&progress::InstallTask>,
+) -> Result<InstalledPluginInfo, AppError> {
+    let plugin_dir = store.plugin_dir(&plugin.id)?;
+    let dir = store.plugins_dir();
+
+    let meta_json = serde_json::to_string_pretty(&plugin)
+        .map_err(|e| AppError::internal(format!("序列化元数据失败: {e}")))?;
+
+    // 沿用首次安装时间，并记录被替换的版本
+    let current = match read_meta(&plugin_dir.join("meta.json")).await {
+        Some(meta) => Some((state::read(&plugin_dir, &meta).await, meta.version)),
+        None => None,
+    };
+    let new_state =
+        state::PluginState::replace(current.as_ref().map(|(s, v)| (s, v.as_str())), &plugin);
+
+    if let Some(task) = task {
+        task.phase(progress::InstallPhase::Write)?;
+    }
+
+    let staging = staging::create(dir, &plugin.id).await?;
+    let mut prepared =
+        prepare_staging(&plugin_dir, &staging, bundle_bytes, &meta_json, &new_state).await;
+    // 换入前最后一次检查取消，取消后暂存目录会被丢弃
+    if let Some(task) = task {
+        prepared = prepared.and_then(|_| task.check());
+    }
+    staging::finish(dir, &plugin.id, &staging, prepared).await?;
+    sync_index(store, std::slice::from_ref(&plugin.id)).await?;
+
+    read_installed_plugin(&plugin_dir).await.ok_or_else(|| {
+        AppError::internal(format!("读取已安装插件 {} 失败", plugin.id)).with_plugin(&plugin.id)
+    })
+}
+
+/// 在暂存目录中写入新版本，并带上当前版本的备份与状态文件
+async fn prepare_staging(
+    plugin_dir: &std::path::Path,
+    staging: &std::path::Path,
+    bundle_bytes: &[u8],
+    meta_json: &str,
+    plugin_state: &state::PluginState,
+) -> Result<(), AppError> {
+    tokio::fs::write(staging.join("bundle.mjs"), bundle_bytes)
+        .await
+        .map_err(|e| AppError::io(format!("写入 bundle.mjs 失败: {e}")))?;
+    tokio::fs::write(staging.join("meta.json"), meta_json)
+        .await
+        .map_err(|e| AppError::io(format!("写入 meta.json 失败: {e}")))?;
+    state::write(staging, plugin_state).await?;
+
+    if staging::is_complete(plugin_dir) {
+        staging::carry_over(
+            plugin_dir,
+            staging,
+            &[
+                ("bundle.mjs", "bundle.mjs.bak"),
+                ("meta.json", "meta.json.bak"),
+            ],
+        )
+        .await?;
+    }
+
+    let state: Vec<(&str, &str)> = STATE_FILES.iter().map(|f| (*f, *f)).collect();
+    staging::carry_over(plugin_dir, staging, &state).await
+}
+
+// ── 插件版本 command ────────────────────────────────────────
+
+/// 包的单个可用版本
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginVersionInfo {
+    pub version: String,
+    /// 发布时间（ISO 8601）
+    pub published_at: Option<String>,
+    /// 指向该版本的 dist-tag（如 latest）
+    pub dist_tags: Vec<String>,
+}
+
+impl PluginStore {
+    /// 列出包的所有可用版本，按版本号从高到低排列
+    ///
+    /// 传入 registry（插件来自的 registry）时只查询它，否则按配置的优先级回退。
+    pub async fn list_plugin_versions(
+        &self,
+        package_name: String,
+        registry: Option<String>,
+    ) -> Result<Vec<PluginVersionInfo>, AppError> {
+        let config = load_config(self).await;
+        let client = registry_client(self, &config).await?;
+        let registries = match registry {
+            Some(registry) => vec![registry],
+            None => config.registries_for(&package_name),
+        };
+        let detail = fetch_package_detail_from(&client, &registries, &package_name).await;
+        let (_, detail) = redact_err(self, detail).await?;
+
+        let mut versions: Vec<PluginVersionInfo> = detail
+            .versions
+            .as_ref()
+            .map(|v| v.keys().cloned().collect::<Vec<_>>())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|version| PluginVersionInfo {
+                published_at: detail.time.as_ref().and_then(|t| t.get(&version)).cloned(),
+                dist_tags: detail
+                    .dist_tags
+                    .as_ref()
+                    .map(|tags| {
+                        tags.iter()
+                            .filter(|(_, v)| **v == version)
+                            .map(|(tag, _)| tag.clone())
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+                version,
+            })
+            .collect();
+
+        // 按 semver 从高到低排列，无法解析的版本按发布时间排在最后
+        versions.sort_by(|a, b| {
+            match (
+                version::parse_version(&a.version),
+                version::parse_version(&b.version),
+            ) {
+                (Some(va), Some(vb)) => vb.cmp_precedence(&va),
+                (Some(_), None) => std::cmp::Ordering::Less,
+                (None, Some(_)) => std::cmp::Ordering::Greater,
+                (None, None) => b.published_at.cmp(&a.published_at),
+            }
+        });
+        Ok(versions)
+    }
+}
+
+#[tauri::command]
+pub async fn list_plugin_versions(
+    app: tauri::AppHandle,
+    package_name: String,
+    registry: Option<String>,
+) -> Result<Vec<PluginVersionInfo>, AppError> {
+    PluginStore::from_app(&app)?
+        .list_plugin_versions(package_name, registry)
+        .await
+}
+
+impl PluginStore {
+    /// 安装指定版本的插件
+    pub async fn install_plugin_version(
+        &self,
+        plugin: PluginMeta,
+        version: String,
+    ) -> Result<InstalledPluginInfo, AppError> {
+        let guard = self.tasks().register(&plugin.id)?;
+        let result = install_from_registry(self, &plugin, Some(&version), &guard.task(self)).await;
+        redact_err(self, result).await
+    }
+}
+
+#[tauri::command]
+pub async fn install_plugin_version(
+    app: tauri::AppHandle,
+    plugin: PluginMeta,
+    version: String,
+) -> Result<InstalledPluginInfo, AppError> {
+    PluginStore::from_app(&app)?
+        .install_plugin_version(plugin, version)
+        .await
+}
+
+/// 取消正在进行的安装，没有对应任务时返回 false
+#[tauri::command]
+pub fn cancel_plugin_install(app: tauri::AppHandle, plugin_id: String) -> bool {
+    app.state::<InstallTasks>().cancel(&plugin_id)
+}
+
+/// 插件版本锁定（存放在插件目录下的 pin.json）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginPin {
+    pub version: String,
+    pub pinned_at: u64,
+}
+
+impl PluginStore {
+    /// 锁定插件版本；`version` 为空时解除锁定。锁定的插件不会出现在更新列表中
+    ///
+    /// 锁定的版本与已安装的版本不同时，先安装锁定的版本，安装失败则不锁定。
+    pub async fn set_plugin_pin(
+        &self,
+        plugin_id: String,
+        version: Option<String>,
+    ) -> Result<(), AppError> {
+        let plugin_dir = self.plugin_dir(&plugin_id)?;
+        if !plugin_dir.join("meta.json").exists() {
+            return Err(
+                AppError::not_found(format!("插件未安装: {plugin_id}")).with_plugin(&plugin_id)
+            );
+        }
+
+        let pin_path = plugin_dir.join("pin.json");
+        if let Some(version) = &version {
+            let result = check_pin_version(self, &plugin_dir, &plugin_id, version).await;
+            if let Some(installed) = redact_err(self, result).await? {
+                self.install_plugin_version(installed, version.clone())
+                    .await?;
+            }
+        }
+        let result = match version {
+            Some(version) => {
+                let pin = PluginPin {
+                    version,
+                    pinned_at: now_ms(),
+                };
+                let json = serde_json::to_string_pretty(&pin)
+                    .map_err(|e| AppError::internal(format!("序列化版本锁定失败: {e}")))?;
+                tokio::fs::write(&pin_path, json)
+                    .await
+                    .map_err(|e| AppError::io(format!("写入 pin.json 失败: {e}")))
+            }
+            None => {
+                if !pin_path.exists() {
+                    return Ok(());
+                }
+                tokio::fs::remove_file(&pin_path)
+                    .await
+                    .map_err(|e| AppError::io(format!("删除 pin.json 失败: {e}")))
+            }
+        };
+        let synced = sync_index(self, std::slice::from_ref(&plugin_id)).await;
+        result.and(synced)
+    }
+}
+
+/// 锁定的版本必须是合法的语义化版本，且是该包已发布的版本
+///
+/// 与已安装的版本不同时返回已安装插件的信息，由调用方安装锁定的版本。
+async fn check_pin_version(
+    store: &PluginStore,
+    plugin_dir: &std::path::Path,
+    plugin_id: &str,
+    version: &str,
+) -> Result<Option<PluginMeta>, AppError> {
+    if version::parse_version(version).is_none() {
+        return Err(
+            AppError::invalid_input(format!("{version} 不是合法的语义化版本"))
+                .with_plugin(plugin_id),
+        );
+    }
+    let meta = read_meta(&plugin_dir.join("meta.json"))
+        .await
+        .ok_or_else(|| {
+            AppError::internal(format!("读取已安装插件 {plugin_id} 失败")).with_plugin(plugin_id)
+        })?;
+    // 锁定当前安装的版本无需联网
+    if meta.version == version {
+        return Ok(None);
+    }
+    if meta.origin.is_some() {
+        return Err(AppError::invalid_input(format!(
+            "插件 {plugin_id} 从本地文件安装，只能锁定当前版本 {}",
+            meta.version
+        ))
+        .with_plugin(plugin_id));
+    }
+
+    let config = load_config(store).await;
+    let client = registry_client(store, &config).await?;
+    let registries = match &meta.registry {
+        Some(registry) => vec![registry.clone()],
+        None => config.registries_for(&meta.package_name),
+    };
+    let (_, detail) = fetch_package_detail_from(&client, &registries, &meta.package_name).await?;
+    let published = detail
         .versions
         .as_ref()
-        .and_then(|v| v.get(&latest_version))
-        .and_then(|v| v.dist.as_ref())
-        .and_then(|d| d.tarball.as_ref())
-        .ok_or_else(|| "找不到 tarball URL".to_string())?
-        .clone();
-
-    // 下载 tarball
-    let tarball_resp = client
-        .get(&tarball_url)
-        .send()
+        .is_some_and(|versions| versions.contains_key(version));
+    if !published {
+        return Err(AppError::not_found(format!(
+            "包 {} 没有发布版本 {version}",
+            meta.package_name
+        ))
+        .with_plugin(plugin_id)
+        .with_package(&meta.package_name));
+    }
+    Ok(Some(meta))
+}
+
+#[tauri::command]
+pub async fn set_plugin_pin(
+    app: tauri::AppHandle,
+    plugin_id: String,
+    version: Option<String>,
+) -> Result<(), AppError> {
+    PluginStore::from_app(&app)?
+        .set_plugin_pin(plugin_id, version)
         .await
-        .map_err(|e| format!("下载 tarball 失败: {e}"))?;
+}
+
+/// 插件停用状态（disabled.json，存在即表示已停用）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDisabled {
+    pub disabled_at: u64,
+}
+
+impl PluginStore {
+    /// 启用或停用插件；停用的插件保留安装文件，但不再提供 bundle
+    pub async fn set_plugin_enabled(
+        &self,
+        plugin_id: String,
+        enabled: bool,
+    ) -> Result<InstalledPluginInfo, AppError> {
+        let plugin_dir = self.plugin_dir(&plugin_id)?;
+        if !plugin_dir.join("meta.json").exists() {
+            return Err(
+                AppError::not_found(format!("插件未安装: {plugin_id}")).with_plugin(&plugin_id)
+            );
+        }
+
+        let disabled_path = plugin_dir.join("disabled.json");
+        if enabled {
+            if disabled_path.exists() {
+                tokio::fs::remove_file(&disabled_path)
+                    .await
+                    .map_err(|e| AppError::io(format!("删除 disabled.json 失败: {e}")))?;
+            }
+        } else {
+            let disabled = PluginDisabled {
+                disabled_at: now_ms(),
+            };
+            let json = serde_json::to_string_pretty(&disabled)
+                .map_err(|e| AppError::internal(format!("序列化停用状态失败: {e}")))?;
+            tokio::fs::write(&disabled_path, json)
+                .await
+                .map_err(|e| AppError::io(format!("写入 disabled.json 失败: {e}")))?;
+        }
+        sync_index(self, std::slice::from_ref(&plugin_id)).await?;
+
+        read_installed_plugin(&plugin_dir).await.ok_or_else(|| {
+            AppError::internal(format!("读取已安装插件 {plugin_id} 失败")).with_plugin(&plugin_id)
+        })
+    }
+}
 
-    let tarball_bytes = tarball_resp
-        .bytes()
+#[tauri::command]
+pub async fn set_plugin_enabled(
+    app: tauri::AppHandle,
+    plugin_id: String,
+    enabled: bool,
+) -> Result<InstalledPluginInfo, AppError> {
+    PluginStore::from_app(&app)?
+        .set_plugin_enabled(plugin_id, enabled)
         .await
-        .map_err(|e| format!("读取 tarball 失败: {e}"))?;
+}
 
-    // 从 tarball 中提取 bundle 文件
-    let bundle_tar_path = format!("package/{}", plugin.bundle_file);
-    let bundle_bytes = extract_bytes_from_tarball(&tarball_bytes, &bundle_tar_path)
-        .ok_or_else(|| format!("tarball 中未找到 {}", bundle_tar_path))?;
+impl PluginStore {
+    /// 回滚到上一个安装的版本（与备份互换，再次回滚即可恢复）
+    pub async fn rollback_plugin(
+        &self,
+        plugin_id: String,
+    ) -> Result<InstalledPluginInfo, AppError> {
+        let plugin_dir = self.plugin_dir(&plugin_id)?;
+        let dir = self.plugins_dir();
+
+        if !plugin_dir.join("bundle.mjs.bak").exists() || !plugin_dir.join("meta.json.bak").exists()
+        {
+            return Err(
+                AppError::not_found(format!("插件 {plugin_id} 没有可回滚的备份"))
+                    .with_plugin(&plugin_id),
+            );
+        }
+
+        // 在暂存目录中互换当前版本与备份，再原子换入
+        let staging = staging::create(dir, &plugin_id).await?;
+        let mut files = vec![
+            ("bundle.mjs.bak", "bundle.mjs"),
+            ("meta.json.bak", "meta.json"),
+            ("bundle.mjs", "bundle.mjs.bak"),
+            ("meta.json", "meta.json.bak"),
+        ];
+        files.extend(STATE_FILES.iter().map(|f| (*f, *f)));
+        let mut prepared = staging::carry_over(&plugin_dir, &staging, &files).await;
+
+        // 回滚也记为一次更新：上一个版本为回滚前的当前版本
+        let current = read_meta(&plugin_dir.join("meta.json")).await;
+        let restored = read_meta(&plugin_dir.join("meta.json.bak")).await;
+        if let (Ok(()), Some(current), Some(restored)) = (&prepared, current, restored) {
+            let current_state = state::read(&plugin_dir, &current).await;
+            let new_state =
+                state::PluginState::replace(Some((&current_state, &current.version)), &restored);
+            prepared = state::write(&staging, &new_state).await;
+        }
+        staging::finish(dir, &plugin_id, &staging, prepared).await?;
+        sync_index(self, std::slice::from_ref(&plugin_id)).await?;
 
-    // 写入 bundle.mjs
-    let bundle_path = plugin_dir.join("bundle.mjs");
-    tokio::fs::write(&bundle_path, &bundle_bytes)
+        read_installed_plugin(&plugin_dir).await.ok_or_else(|| {
+            AppError::internal(format!("读取已安装插件 {plugin_id} 失败")).with_plugin(&plugin_id)
+        })
+    }
+}
+
+#[tauri::command]
+pub async fn rollback_plugin(
+    app: tauri::AppHandle,
+    plugin_id: String,
+) -> Result<InstalledPluginInfo, AppError> {
+    PluginStore::from_app(&app)?
+        .rollback_plugin(plugin_id)
         .await
-        .map_err(|e| format!("写入 bundle.mjs 失败: {e}"))?;
+}
 
-    // 写入 meta.json
-    let meta_json =
-        serde_json::to_string_pretty(&plugin).map_err(|e| format!("序列化元数据失败: {e}"))?;
-    tokio::fs::write(plugin_dir.join("meta.json"), meta_json)
+// ── registry 凭据 command ───────────────────────────────────
+
+impl PluginStore {
+    /// 列出已保存凭据的 registry（不含密钥）
+    ///
+    /// 凭据文件无法还原时列表为空并带上警告，可重新保存凭据或清除全部凭据来覆盖它。
+    pub async fn list_registry_auth(&self) -> RegistryAuthList {
+        let loaded = auth::load(self.plugins_dir()).await;
+        RegistryAuthList {
+            registries: loaded.credentials.summaries(),
+            warning: loaded.warning,
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn list_registry_auth(app: tauri::AppHandle) -> Result<RegistryAuthList, AppError> {
+    Ok(PluginStore::from_app(&app)?.list_registry_auth().await)
+}
+
+impl PluginStore {
+    /// 设置 registry 的凭据，auth 为空时删除
+    pub async fn set_registry_auth(
+        &self,
+        registry: String,
+        auth: Option<RegistryAuth>,
+    ) -> Result<(), AppError> {
+        let dir = self.plugins_dir();
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| AppError::io(format!("无法创建插件目录: {e}")))?;
+
+        let mut credentials = auth::load(dir).await.credentials;
+        credentials.set(&registry, auth)?;
+        auth::save(dir, &credentials).await?;
+
+        // 凭据变化后需要重新请求之前可能失败的私有包
+        expire_cache(&dir.join("registry-cache.json")).await;
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub async fn set_registry_auth(
+    app: tauri::AppHandle,
+    registry: String,
+    auth: Option<RegistryAuth>,
+) -> Result<(), AppError> {
+    PluginStore::from_app(&app)?
+        .set_registry_auth(registry, auth)
         .await
-        .map_err(|e| format!("写入 meta.json 失败: {e}"))?;
+}
 
-    let now = now_ms();
-    Ok(InstalledPluginInfo {
-        meta: plugin,
-        installed_at: now,
-        updated_at: now,
-        local_bundle_path: bundle_path.to_string_lossy().to_string(),
-        enabled: true,
-    })
+impl PluginStore {
+    /// 删除全部 registry 凭据（包括无法还原的凭据文件）
+    pub async fn clear_registry_auth(&self) -> Result<(), AppError> {
+        let dir = self.plugins_dir();
+        auth::clear(dir).await?;
+        expire_cache(&dir.join("registry-cache.json")).await;
+        Ok(())
+    }
 }
 
-// ── 插件卸载 command ────────────────────────────────────────
+#[tauri::command]
+pub async fn clear_registry_auth(app: tauri::AppHandle) -> Result<(), AppError> {
+    PluginStore::from_app(&app)?.clear_registry_auth().await
+}
+
+impl PluginStore {
+    /// 从 npmrc 文件导入 registry 凭据，返回导入的 registry
+    pub async fn import_npmrc_auth(
+        &self,
+        npmrc: std::path::PathBuf,
+    ) -> Result<Vec<String>, AppError> {
+        let dir = self.plugins_dir();
+        let content = tokio::fs::read_to_string(&npmrc)
+            .await
+            .map_err(|e| AppError::file(format!("读取 {} 失败: {e}", npmrc.display()), &e))?;
+
+        let imported = auth::parse_npmrc(&content, |name| std::env::var(name).ok());
+        if imported.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        tokio::fs::create_dir_all(&dir)
+            .await
+            .map_err(|e| AppError::io(format!("无法创建插件目录: {e}")))?;
+        let mut credentials = auth::load(dir).await.credentials;
+        let registries = credentials.merge(imported);
+        auth::save(dir, &credentials).await?;
+        expire_cache(&dir.join("registry-cache.json")).await;
+        Ok(registries)
+    }
+}
 
+/// 从 ~/.npmrc 导入 registry 凭据
 #[tauri::command]
-pub async fn uninstall_plugin(app: tauri::AppHandle, plugin_id: String) -> Result<(), String> {
-    let dir = plugins_dir(&app)?;
-    let plugin_dir = dir.join(&plugin_id);
+pub async fn import_npmrc_auth(app: tauri::AppHandle) -> Result<Vec<String>, AppError> {
+    let npmrc = app
+        .path()
+        .home_dir()
+        .map_err(|e| AppError::io(format!("无法获取用户目录: {e}")))?
+        .join(".npmrc");
+    PluginStore::from_app(&app)?.import_npmrc_auth(npmrc).await
+}
+
+// ── 插件卸载 command ────────────────────────────────────────
+
+impl PluginStore {
+    /// 卸载插件，返回实际被删除的插件 id
+    ///
+    /// 若仍有已安装的插件依赖它，默认拒绝卸载；`cascade` 为 true 时连同依赖者一起卸载。
+    pub async fn uninstall_plugin(
+        &self,
+        plugin_id: String,
+        cascade: bool,
+    ) -> Result<Vec<String>, AppError> {
+        let plugin_dir = self.plugin_dir(&plugin_id)?;
+
+        if !plugin_dir.exists() {
+            // 目录已被删除时同时清理索引中残留的记录
+            sync_index(self, std::slice::from_ref(&plugin_id)).await?;
+            return Ok(Vec::new());
+        }
+
+        let installed = self.installed_plugins().await?;
+        let deps_of: std::collections::HashMap<String, Vec<String>> = installed
+            .into_iter()
+            .map(|p| (p.meta.id, p.meta.dependencies))
+            .collect();
+
+        let mut removed = dependency::collect_dependents(&plugin_id, &deps_of);
+        if !removed.is_empty() && !cascade {
+            return Err(AppError::new(
+                ErrorCode::Dependency,
+                format!(
+                    "插件 {} 仍被以下插件依赖: {}",
+                    plugin_id,
+                    removed.join(", ")
+                ),
+            )
+            .with_plugin(plugin_id));
+        }
+        removed.push(plugin_id);
 
-    if !plugin_dir.exists() {
-        return Ok(());
+        let mut result = Ok(());
+        for id in &removed {
+            result = remove_plugin_dir(self, id).await;
+            if result.is_err() {
+                break;
+            }
+        }
+        let synced = sync_index(self, &removed).await;
+        result.and(synced).map(|_| removed)
     }
+}
+
+#[tauri::command]
+pub async fn uninstall_plugin(
+    app: tauri::AppHandle,
+    plugin_id: String,
+    cascade: Option<bool>,
+) -> Result<Vec<String>, AppError> {
+    PluginStore::from_app(&app)?
+        .uninstall_plugin(plugin_id, cascade.unwrap_or(false))
+        .await
+}
 
-    tokio::fs::remove_dir_all(&plugin_dir)
+async fn remove_plugin_dir(store: &PluginStore, plugin_id: &str) -> Result<(), AppError> {
+    tokio::fs::remove_dir_all(store.plugin_dir(plugin_id)?)
         .await
-        .map_err(|e| format!("删除插件目录失败: {e}"))
+        .map_err(|e| AppError::io(format!("删除插件目录失败: {e}")).with_plugin(plugin_id))
+}
+
+// ── 插件依赖关系图 command ──────────────────────────────────
+
+impl PluginStore {
+    /// 已安装插件及其（传递）依赖组成的关系图
+    pub async fn dependency_graph(&self) -> Result<DependencyGraph, AppError> {
+        let installed = self.installed_plugins().await?;
+        // 注册表仅用于补充未安装依赖的信息，获取失败不影响结果
+        let registry = self.fetch_registry(false).await.unwrap_or_default().plugins;
+
+        let installed_ids: std::collections::HashSet<String> =
+            installed.iter().map(|p| p.meta.id.clone()).collect();
+        let mut known: std::collections::HashMap<String, PluginMeta> =
+            registry.into_iter().map(|p| (p.id.clone(), p)).collect();
+        for p in installed {
+            known.insert(p.meta.id.clone(), p.meta);
+        }
+
+        let mut nodes = Vec::new();
+        let mut edges = Vec::new();
+        let mut visited = std::collections::HashSet::new();
+        let mut queue: Vec<String> = installed_ids.iter().cloned().collect();
+        queue.sort();
+
+        while let Some(id) = queue.pop() {
+            if !visited.insert(id.clone()) {
+                continue;
+            }
+            let meta = known.get(&id);
+            nodes.push(dependency::DependencyNode {
+                id: id.clone(),
+                title: meta.map(|m| m.title.clone()),
+                version: meta.map(|m| m.version.clone()),
+                installed: installed_ids.contains(&id),
+            });
+            for dep in meta.map(|m| m.dependencies.clone()).unwrap_or_default() {
+                edges.push(dependency::DependencyEdge {
+                    from: id.clone(),
+                    to: dep.clone(),
+                });
+                queue.push(dep);
+            }
+        }
+
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(DependencyGraph { nodes, edges })
+    }
+}
+
+#[tauri::command]
+pub async fn get_plugin_dependency_graph(
+    app: tauri::AppHandle,
+) -> Result<DependencyGraph, AppError> {
+    PluginStore::from_app(&app)?.dependency_graph().await
 }
 
 // ── 获取已安装插件列表 command ──────────────────────────────
 
+impl PluginStore {
+    /// 已安装的插件（读取索引）
+    pub async fn installed_plugins(&self) -> Result<Vec<InstalledPluginInfo>, AppError> {
+        Ok(load_index(self.plugins_dir())
+            .await?
+            .plugins
+            .into_values()
+            .collect())
+    }
+}
+
 #[tauri::command]
 pub async fn get_installed_plugins(
     app: tauri::AppHandle,
-) -> Result<Vec<InstalledPluginInfo>, String> {
-    let dir = plugins_dir(&app)?;
+) -> Result<Vec<InstalledPluginInfo>, AppError> {
+    PluginStore::from_app(&app)?.installed_plugins().await
+}
 
+/// 读取已安装插件索引，索引缺失或损坏时从磁盘重建
+///
+/// 损坏的索引先移到 installed-plugins.json.corrupt 再重建，损坏的原因可通过 repair_index 查看。
+async fn load_index(dir: &std::path::Path) -> Result<index::InstalledIndex, AppError> {
+    if let Ok(Some(installed)) = index::read(dir).await {
+        return Ok(installed);
+    }
     if !dir.exists() {
-        return Ok(Vec::new());
+        return Ok(index::InstalledIndex::default());
     }
 
-    let mut entries = tokio::fs::read_dir(&dir)
-        .await
-        .map_err(|e| format!("读取插件目录失败: {e}"))?;
+    let _lock = index::lock(dir).await?;
+    // 等待锁期间可能已由其他命令重建
+    match index::read(dir).await {
+        Ok(Some(installed)) => return Ok(installed),
+        Ok(None) => {}
+        Err(_) => index::set_aside(dir).await?,
+    }
+    let scan = scan_plugins_dir(dir).await?;
+    index::write(dir, &scan.index).await?;
+    Ok(scan.index)
+}
+
+/// 插件目录变化后，从磁盘重新读取这些插件并更新索引
+///
+/// 更新失败时删除索引（下次读取时重建）并返回错误，此时插件目录的修改已经完成。
+async fn sync_index(store: &PluginStore, plugin_ids: &[String]) -> Result<(), AppError> {
+    let dir = store.plugins_dir();
+    let result = async {
+        let _lock = index::lock(dir).await?;
+        // 索引缺失或损坏时整体重建，损坏的索引先移走
+        let mut installed = match index::read(dir).await {
+            Ok(Some(installed)) => installed,
+            Ok(None) => scan_plugins_dir(dir).await?.index,
+            Err(_) => {
+                index::set_aside(dir).await?;
+                scan_plugins_dir(dir).await?.index
+            }
+        };
+        for id in plugin_ids {
+            match read_installed_plugin(&dir.join(id)).await {
+                Some(info) => installed.plugins.insert(id.clone(), info),
+                None => installed.plugins.remove(id),
+            };
+        }
+        index::write(dir, &installed).await
+    }
+    .await;
+    result.map_err(|e| {
+        index::invalidate(dir);
+        e.prefixed("插件目录已更新，但更新插件索引失败（下次读取时重建）")
+    })
+}
+
+/// 扫描插件目录的结果
+struct PluginsDirScan {
+    index: index::InstalledIndex,
+    /// 不完整的插件目录（缺少 meta.json 或 bundle.mjs）
+    orphaned: Vec<String>,
+    /// 无法读取的插件目录
+    corrupt: Vec<CorruptPlugin>,
+}
+
+async fn scan_plugins_dir(dir: &std::path::Path) -> Result<PluginsDirScan, AppError> {
+    let mut scan = PluginsDirScan {
+        index: index::InstalledIndex::default(),
+        orphaned: Vec::new(),
+        corrupt: Vec::new(),
+    };
+    if !dir.exists() {
+        return Ok(scan);
+    }
 
-    let mut plugins = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir)
+        .await
+        .map_err(|e| AppError::io(format!("读取插件目录失败: {e}")))?;
 
     while let Some(entry) = entries
         .next_entry()
         .await
-        .map_err(|e| format!("遍历插件目录失败: {e}"))?
+        .map_err(|e| AppError::io(format!("遍历插件目录失败: {e}")))?
     {
         let path = entry.path();
+        let name = entry.file_name().to_string_lossy().to_string();
 
-        if !path.is_dir() {
+        // 跳过非目录以及 .staging / .old 等内部目录
+        if !path.is_dir() || name.starts_with('.') {
             continue;
         }
-
-        let meta_path = path.join("meta.json");
-        let bundle_path = path.join("bundle.mjs");
-
-        if !meta_path.exists() || !bundle_path.exists() {
+        if !staging::is_complete(&path) {
+            scan.orphaned.push(name);
             continue;
         }
 
-        let meta_content = match tokio::fs::read_to_string(&meta_path).await {
-            Ok(content) => content,
-            Err(_) => continue,
-        };
+        match inspect_installed_plugin(&path).await {
+            Ok(plugin) => {
+                scan.index.plugins.insert(name, plugin);
+            }
+            Err(error) => scan.corrupt.push(CorruptPlugin { id: name, error }),
+        }
+    }
 
-        let meta: PluginMeta = match serde_json::from_str(&meta_content) {
-            Ok(m) => m,
-            Err(_) => continue,
-        };
+    scan.orphaned.sort();
+    scan.corrupt.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(scan)
+}
 
-        let (installed_at, updated_at) = match tokio::fs::metadata(&meta_path).await {
-            Ok(file_meta) => {
-                let created = file_meta
-                    .created()
-                    .unwrap_or(SystemTime::UNIX_EPOCH)
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis() as u64;
-                let modified = file_meta
-                    .modified()
-                    .unwrap_or(SystemTime::UNIX_EPOCH)
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_or_default()
-                    .as_millis() as u64;
-                (created, modified)
-            }
-            Err(_) => {
-                let now = now_ms();
-                (now, now)
+/// 无法读取的插件目录
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct CorruptPlugin {
+    /// 目录名
+    pub id: String,
+    pub error: AppError,
+}
+
+/// 重建索引的结果
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct IndexRepairReport {
+    /// 重建后的已安装插件数量
+    pub installed: usize,
+    /// 磁盘上存在但原索引中没有的插件
+    pub added: Vec<String>,
+    /// 原索引中有但磁盘上已不存在或无法读取的插件
+    pub dropped: Vec<String>,
+    /// 不完整的插件目录（缺少 meta.json 或 bundle.mjs）
+    pub orphaned: Vec<String>,
+    /// 无法读取的插件目录
+    pub corrupt: Vec<CorruptPlugin>,
+    /// 原索引无法读取的原因（如文件已损坏）；之前读取时已自动重建的，取自移走的
+    /// installed-plugins.json.corrupt
+    pub index_error: Option<AppError>,
+}
+
+impl PluginStore {
+    /// 从磁盘重建已安装插件索引，并报告不完整或损坏的插件目录（不会删除任何目录）
+    pub async fn repair_index(&self) -> Result<IndexRepairReport, AppError> {
+        let dir = self.plugins_dir();
+        let _lock = index::lock(dir).await?;
+
+        let (previous, index_error) = match index::read(dir).await {
+            Ok(previous) => (
+                previous.unwrap_or_default(),
+                index::corrupt_reason(dir).await,
+            ),
+            Err(e) => {
+                index::set_aside(dir).await?;
+                (index::InstalledIndex::default(), Some(e))
             }
         };
+        let scan = scan_plugins_dir(dir).await?;
+        index::write(dir, &scan.index).await?;
+
+        Ok(IndexRepairReport {
+            installed: scan.index.plugins.len(),
+            added: scan
+                .index
+                .plugins
+                .keys()
+                .filter(|id| !previous.plugins.contains_key(*id))
+                .cloned()
+                .collect(),
+            dropped: previous
+                .plugins
+                .keys()
+                .filter(|id| !scan.index.plugins.contains_key(*id))
+                .cloned()
+                .collect(),
+            orphaned: scan.orphaned,
+            corrupt: scan.corrupt,
+            index_error,
+        })
+    }
+}
 
-        plugins.push(InstalledPluginInfo {
-            meta,
-            installed_at,
-            updated_at,
-            local_bundle_path: bundle_path.to_string_lossy().to_string(),
-            enabled: true,
-        });
+#[tauri::command]
+pub async fn repair_plugin_index(app: tauri::AppHandle) -> Result<IndexRepairReport, AppError> {
+    PluginStore::from_app(&app)?.repair_index().await
+}
+
+async fn read_installed_plugin(path: &std::path::Path) -> Option<InstalledPluginInfo> {
+    inspect_installed_plugin(path).await.ok()
+}
+
+/// 读取插件目录，失败时返回原因
+async fn inspect_installed_plugin(path: &std::path::Path) -> Result<InstalledPluginInfo, AppError> {
+    let meta_path = path.join("meta.json");
+    let bundle_path = path.join("bundle.mjs");
+
+    if !meta_path.exists() || !bundle_path.exists() {
+        return Err(AppError::not_found("缺少 meta.json 或 bundle.mjs"));
+    }
+
+    let content = tokio::fs::read_to_string(&meta_path)
+        .await
+        .map_err(|e| AppError::io(format!("读取 meta.json 失败: {e}")))?;
+    let meta: PluginMeta = serde_json::from_str(&content)
+        .map_err(|e| AppError::parse(format!("解析 meta.json 失败: {e}")))?;
+    let dir_name = path.file_name().unwrap_or_default().to_string_lossy();
+    if meta.id != dir_name {
+        return Err(AppError::new(
+            ErrorCode::InvalidManifest,
+            format!("meta.json 中的 id {} 与目录名不一致", meta.id),
+        ));
     }
+    let plugin_state = state::read(path, &meta).await;
+
+    let pinned_version = match tokio::fs::read_to_string(path.join("pin.json")).await {
+        Ok(content) => serde_json::from_str::<PluginPin>(&content)
+            .ok()
+            .map(|pin| pin.version),
+        Err(_) => None,
+    };
+
+    let backup_version = read_meta(&path.join("meta.json.bak"))
+        .await
+        .map(|meta| meta.version);
+
+    Ok(InstalledPluginInfo {
+        meta,
+        installed_at: plugin_state.installed_at,
+        updated_at: plugin_state.updated_at,
+        previous_version: plugin_state.previous_version,
+        local_bundle_path: bundle_path.to_string_lossy().to_string(),
+        enabled: !path.join("disabled.json").exists(),
+        pinned_version,
+        backup_version,
+    })
+}
+
+async fn read_meta(meta_path: &std::path::Path) -> Option<PluginMeta> {
+    let content = tokio::fs::read_to_string(meta_path).await.ok()?;
+    serde_json::from_str(&content).ok()
+}
 
-    Ok(plugins)
+/// 插件是否已停用
+fn is_disabled(store: &PluginStore, plugin_id: &str) -> Result<bool, AppError> {
+    Ok(store.plugin_dir(plugin_id)?.join("disabled.json").exists())
 }
 
 // ── 获取插件 bundle 路径 command ────────────────────────────
 
+impl PluginStore {
+    pub async fn plugin_bundle_path(&self, plugin_id: String) -> Result<String, AppError> {
+        let bundle_path = self.plugin_dir(&plugin_id)?.join("bundle.mjs");
+
+        if !bundle_path.exists() {
+            return Err(
+                AppError::not_found(format!("插件 bundle 文件不存在: {plugin_id}"))
+                    .with_plugin(&plugin_id),
+            );
+        }
+        if is_disabled(self, &plugin_id)? {
+            return Err(
+                AppError::new(ErrorCode::Disabled, format!("插件已停用: {plugin_id}"))
+                    .with_plugin(&plugin_id),
+            );
+        }
+
+        Ok(bundle_path.to_string_lossy().to_string())
+    }
+}
+
 #[tauri::command]
 pub async fn get_plugin_bundle_path(
     app: tauri::AppHandle,
     plugin_id: String,
-) -> Result<String, String> {
-    let dir = plugins_dir(&app)?;
-    let bundle_path = dir.join(&plugin_id).join("bundle.mjs");
-
-    if !bundle_path.exists() {
-        return Err(format!("插件 bundle 文件不存在: {plugin_id}"));
-    }
-
-    Ok(bundle_path.to_string_lossy().to_string())
+) -> Result<String, AppError> {
+    PluginStore::from_app(&app)?
+        .plugin_bundle_path(plugin_id)
+        .await
 }
 
 // ── 读取插件 bundle 内容 command ────────────────────────────
 
+impl PluginStore {
+    pub async fn read_plugin_bundle(&self, plugin_id: String) -> Result<String, AppError> {
+        let bundle_path = self.plugin_dir(&plugin_id)?.join("bundle.mjs");
+
+        if !bundle_path.exists() {
+            return Err(
+                AppError::not_found(format!("插件 bundle 文件不存在: {plugin_id}"))
+                    .with_plugin(&plugin_id),
+            );
+        }
+        if is_disabled(self, &plugin_id)? {
+            return Err(
+                AppError::new(ErrorCode::Disabled, format!("插件已停用: {plugin_id}"))
+                    .with_plugin(&plugin_id),
+            );
+        }
+
+        tokio::fs::read_to_string(&bundle_path)
+            .await
+            .map_err(|e| AppError::io(format!("读取插件 bundle 失败: {e}")).with_plugin(&plugin_id))
+    }
+}
+
 #[tauri::command]
 pub async fn read_plugin_bundle(
     app: tauri::AppHandle,
     plugin_id: String,
-) -> Result<String, String> {
-    let dir = plugins_dir(&app)?;
-    let bundle_path = dir.join(&plugin_id).join("bundle.mjs");
-
-    if !bundle_path.exists() {
-        return Err(format!("插件 bundle 文件不存在: {plugin_id}"));
-    }
-
-    tokio::fs::read_to_string(&bundle_path)
+) -> Result<String, AppError> {
+    PluginStore::from_app(&app)?
+        .read_plugin_bundle(plugin_id)
         .await
-        .map_err(|e| format!("读取插件 bundle 失败: {e}"))
 }
 
 // ── 读取本地 bundle 文件（调试用） ──────────────────────────
 
 #[tauri::command]
-pub async fn read_local_bundle(file_path: String) -> Result<String, String> {
+pub async fn read_local_bundle(file_path: String) -> Result<String, AppError> {
     let path = std::path::Path::new(&file_path);
 
     if !path.exists() {
-        return Err(format!("文件不存在: {file_path}"));
+        return Err(AppError::not_found(format!("文件不存在: {file_path}")));
     }
 
     if path.extension().and_then(|e| e.to_str()) != Some("mjs") {
-        return Err("仅支持 .mjs 文件".to_string());
+        return Err(AppError::invalid_input("仅支持 .mjs 文件"));
     }
 
     tokio::fs::read_to_string(path)
         .await
-        .map_err(|e| format!("读取文件失败: {e}"))
+        .map_err(|e| AppError::file(format!("读取文件失败: {e}"), &e))
 }
 
 // ── 读取本地 plugin.json（调试用） ──────────────────────────
 
-#[tauri::command]
-pub async fn read_local_plugin_json(dir_path: String) -> Result<Vec<PluginMeta>, String> {
-    let dir = std::path::Path::new(&dir_path);
-    let plugin_json_path = dir.join("plugin.json");
-
-    if !plugin_json_path.exists() {
-        return Err(format!("未找到 plugin.json: {}", plugin_json_path.display()));
+impl PluginStore {
+    pub async fn read_local_plugin_json(
+        &self,
+        dir_path: String,
+    ) -> Result<LocalPluginJson, AppError> {
+        let report = check_local_plugin_json(self, &dir_path).await?;
+        let warnings = report.warnings();
+
+        let plugins: Vec<PluginMeta> = report
+            .into_result()?
+            .into_iter()
+            .map(|entry| entry.into_meta("local-debug"))
+            .collect();
+        if plugins.is_empty() {
+            return Err(
+                AppError::new(ErrorCode::InvalidManifest, "未找到有效的插件条目")
+                    .with_diagnostics(warnings),
+            );
+        }
+        Ok(LocalPluginJson { plugins, warnings })
     }
+}
 
-    let content = tokio::fs::read_to_string(&plugin_json_path)
-        .await
-        .map_err(|e| format!("读取 plugin.json 失败: {e}"))?;
-
-    let plugin_json: PluginJson = serde_json::from_str(&content)
-        .map_err(|e| format!("解析 plugin.json 失败: {e}"))?;
-
-    let entries = plugin_json.into_entries();
-    let mut plugins = Vec::new();
+/// read_local_plugin_json 的返回值
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LocalPluginJson {
+    pub plugins: Vec<PluginMeta>,
+    /// plugin.json 中的警告（有错误时返回 AppError，全部诊断见 context.diagnostics）
+    pub warnings: Vec<manifest::Diagnostic>,
+}
 
-    for entry in entries {
-        // 检查 bundle 文件是否存在
-        let bundle_path = dir.join(&entry.bundle);
-        if !bundle_path.exists() {
-            eprintln!("跳过 {}: bundle 文件不存在 {}", entry.id, bundle_path.display());
-            continue;
-        }
+#[tauri::command]
+pub async fn read_local_plugin_json(
+    app: tauri::AppHandle,
+    dir_path: String,
+) -> Result<LocalPluginJson, AppError> {
+    PluginStore::from_app(&app)?
+        .read_local_plugin_json(dir_path)
+        .await
+}
 
-        plugins.push(PluginMeta {
-            id: entry.id,
-            version: entry.version,
-            author: entry.author,
-            homepage: entry.homepage,
-            icon: entry.icon,
-            title: entry.title,
-            subtitle: entry.subtitle,
-            description: entry.description,
-            bg_color: entry.bg_color,
-            text_color: entry.text_color,
-            categories: entry.categories,
-            requires: entry.requires,
-            package_name: "local-debug".to_string(),
-            bundle_file: entry.bundle,
-            downloads: None,
-            rating: None,
-            updated_at: None,
-            created_at: None,
-        });
+impl PluginStore {
+    /// 校验本地插件目录中的 plugin.json，返回所有错误与警告（带 JSON 路径）
+    pub async fn validate_local_plugin_json(
+        &self,
+        dir_path: String,
+    ) -> Result<manifest::ManifestReport, AppError> {
+        check_local_plugin_json(self, &dir_path).await
     }
+}
 
-    if plugins.is_empty() {
-        return Err("未找到有效的插件条目".to_string());
-    }
+#[tauri::command]
+pub async fn validate_local_plugin_json(
+    app: tauri::AppHandle,
+    dir_path: String,
+) -> Result<manifest::ManifestReport, AppError> {
+    PluginStore::from_app(&app)?
+        .validate_local_plugin_json(dir_path)
+        .await
+}
 
-    Ok(plugins)
+/// 校验 plugin.json，并检查每个 bundle 文件是否存在
+async fn check_local_plugin_json(
+    store: &PluginStore,
+    dir_path: &str,
+) -> Result<manifest::ManifestReport, AppError> {
+    let dir = std::path::Path::new(dir_path);
+
+    // 与已安装的同名包不算 id 冲突（调试已安装插件的新版本）
+    let package_name = LocalPackage::Directory(dir.to_path_buf())
+        .package_name(dir)
+        .await?;
+    let installed = installed_packages(store.plugins_dir()).await?;
+    manifest::validate_dir(dir, &package_name, &installed).await
 }
 
 // ── 检查插件更新 command ────────────────────────────────────
 
-#[tauri::command]
-pub async fn check_plugin_updates(
-    app: tauri::AppHandle,
-) -> Result<Vec<PluginMeta>, String> {
-    let installed = get_installed_plugins(app.clone()).await?;
-    let remote_plugins = fetch_plugin_registry(app, false).await?;
-
-    let local_versions: std::collections::HashMap<String, String> = installed
-        .into_iter()
-        .map(|p| (p.meta.id.clone(), p.meta.version.clone()))
-        .collect();
+/// 单个插件的可用更新
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginUpdate {
+    /// 远端最新的插件元数据
+    pub plugin: PluginMeta,
+    /// 本地已安装的版本
+    pub current_version: String,
+    pub kind: UpdateKind,
+}
 
-    let updates: Vec<PluginMeta> = remote_plugins
-        .into_iter()
-        .filter(|remote| {
-            if let Some(local_version) = local_versions.get(&remote.id) {
-                remote.version != *local_version
-            } else {
-                false
-            }
-        })
-        .collect();
+impl PluginStore {
+    /// 检查已安装插件的更新（已锁定版本与从本地文件安装的插件除外）
+    ///
+    /// 远端插件按 (registry, 包名, id) 与已安装的插件对应，其他包或其他 registry 中
+    /// 同 id 的插件不算更新。
+    pub async fn check_updates(&self) -> Result<Vec<PluginUpdate>, AppError> {
+        let installed = self.installed_plugins().await?;
+        let remote_plugins = self.fetch_registry(false).await?.plugins;
+
+        // 已锁定版本或不是从 registry 安装的插件不参与更新
+        let local: std::collections::HashMap<(String, String), PluginMeta> = installed
+            .into_iter()
+            .filter(|p| p.pinned_version.is_none() && p.meta.origin.is_none())
+            .map(|p| ((p.meta.package_name.clone(), p.meta.id.clone()), p.meta))
+            .collect();
+
+        // 按 semver 比较；版本号不合法的插件不视为有更新
+        let updates: Vec<PluginUpdate> = remote_plugins
+            .into_iter()
+            .filter_map(|remote| {
+                let meta = local.get(&(remote.package_name.clone(), remote.id.clone()))?;
+                // 未记录 registry 的旧安装不限制来源
+                if meta.registry.is_some() && meta.registry != remote.registry {
+                    return None;
+                }
+                let kind = version::classify_update(&meta.version, &remote.version)?;
+                Some(PluginUpdate {
+                    current_version: meta.version.clone(),
+                    plugin: remote,
+                    kind,
+                })
+            })
+            .collect();
+
+        Ok(updates)
+    }
+}
 
-    Ok(updates)
+#[tauri::command]
+pub async fn check_plugin_updates(app: tauri::AppHandle) -> Result<Vec<PluginUpdate>, AppError> {
+    PluginStore::from_app(&app)?.check_updates().await
 }
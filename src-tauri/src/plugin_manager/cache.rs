@@ -0,0 +1,127 @@
+use crate::error::AppError;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+// ── tarball 缓存 ────────────────────────────────────────────
+//
+// 以内容哈希（见 integrity::content_key）为文件名存放在 `.cache/tarballs` 下。
+// 读取时更新修改时间，淘汰时按修改时间从旧到新删除（LRU）。
+
+/// 缓存目录（相对 plugins 目录）
+pub const CACHE_DIR: &str = ".cache";
+/// 缓存总大小上限
+const MAX_CACHE_SIZE: u64 = 256 * 1024 * 1024;
+/// 超过该时间未使用的缓存直接淘汰
+const MAX_CACHE_AGE: Duration = Duration::from_secs(30 * 24 * 3600);
+
+fn tarball_dir(plugins_dir: &Path) -> PathBuf {
+    plugins_dir.join(CACHE_DIR).join("tarballs")
+}
+
+fn tarball_path(plugins_dir: &Path, key: &str) -> PathBuf {
+    tarball_dir(plugins_dir).join(format!("{key}.tgz"))
+}
+
+/// 读取缓存的 tarball，命中时刷新其使用时间
+///
+/// 调用方仍需重新校验内容，缓存文件可能已损坏。
+pub async fn read(plugins_dir: &Path, key: &str) -> Option<Vec<u8>> {
+    let path = tarball_path(plugins_dir, key);
+    let bytes = tokio::fs::read(&path).await.ok()?;
+    touch(&path);
+    Some(bytes)
+}
+
+/// 写入缓存（先写临时文件再 rename），随后按策略淘汰
+pub async fn write(plugins_dir: &Path, key: &str, bytes: &[u8]) -> Result<(), AppError> {
+    let dir = tarball_dir(plugins_dir);
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| AppError::io(format!("无法创建缓存目录: {e}")))?;
+
+    let path = tarball_path(plugins_dir, key);
+    let tmp = path.with_extension(format!("tmp-{}", super::now_ms()));
+    tokio::fs::write(&tmp, bytes)
+        .await
+        .map_err(|e| AppError::io(format!("写入缓存失败: {e}")))?;
+    if let Err(e) = tokio::fs::rename(&tmp, &path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(AppError::io(format!("写入缓存失败: {e}")));
+    }
+
+    evict(plugins_dir).await;
+    Ok(())
+}
+
+/// 删除指定缓存（如校验失败的文件）
+pub async fn remove(plugins_dir: &Path, key: &str) {
+    let _ = tokio::fs::remove_file(tarball_path(plugins_dir, key)).await;
+}
+
+/// 淘汰过期缓存，并在总大小超限时删除最久未使用的文件，返回删除的文件数
+pub async fn evict(plugins_dir: &Path) -> usize {
+    let mut files = list(plugins_dir).await;
+    let now = SystemTime::now();
+    let mut removed = 0;
+
+    files.retain(|(path, _, modified)| {
+        let expired = now
+            .duration_since(*modified)
+            .is_ok_and(|age| age > MAX_CACHE_AGE);
+        if expired && std::fs::remove_file(path).is_ok() {
+            removed += 1;
+        }
+        !expired
+    });
+
+    // 最近使用的排在前面，超出上限的部分删除
+    files.sort_by_key(|(_, _, modified)| std::cmp::Reverse(*modified));
+    let mut total = 0u64;
+    for (path, size, _) in files {
+        total += size;
+        if total > MAX_CACHE_SIZE && std::fs::remove_file(&path).is_ok() {
+            removed += 1;
+        }
+    }
+    removed
+}
+
+/// 清空缓存，返回释放的字节数
+pub async fn clear(plugins_dir: &Path) -> Result<u64, AppError> {
+    let freed = list(plugins_dir)
+        .await
+        .iter()
+        .map(|(_, size, _)| size)
+        .sum();
+    let dir = plugins_dir.join(CACHE_DIR);
+    if dir.exists() {
+        tokio::fs::remove_dir_all(&dir)
+            .await
+            .map_err(|e| AppError::io(format!("清空缓存失败: {e}")))?;
+    }
+    Ok(freed)
+}
+
+/// 列出缓存文件：(路径, 大小, 最后使用时间)
+async fn list(plugins_dir: &Path) -> Vec<(PathBuf, u64, SystemTime)> {
+    let mut files = Vec::new();
+    let Ok(mut entries) = tokio::fs::read_dir(tarball_dir(plugins_dir)).await else {
+        return files;
+    };
+    while let Ok(Some(entry)) = entries.next_entry().await {
+        let Ok(meta) = entry.metadata().await else {
+            continue;
+        };
+        if meta.is_file() {
+            let modified = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            files.push((entry.path(), meta.len(), modified));
+        }
+    }
+    files
+}
+
+fn touch(path: &Path) {
+    if let Ok(file) = std::fs::File::options().write(true).open(path) {
+        let _ = file.set_modified(SystemTime::now());
+    }
+}
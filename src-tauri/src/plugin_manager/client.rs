@@ -0,0 +1,209 @@
+use super::auth::Credentials;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+// ── 网络设置 ────────────────────────────────────────────────
+
+/// 插件网络设置（保存在 PluginConfig.network 中）
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct NetworkConfig {
+    /// 代理地址（如 http://proxy.example.com:8080），为空时沿用系统环境变量中的代理
+    pub proxy: Option<String>,
+    /// 不经过代理的主机，规则同 NO_PROXY（如 localhost、.corp.example.com）
+    pub no_proxy: Vec<String>,
+    /// 额外信任的根证书（PEM，可包含多个证书）
+    pub ca_certificates: Option<String>,
+    /// 建立连接的超时时间（秒）
+    pub connect_timeout_secs: u64,
+    /// 两次读取数据之间的最长等待时间（秒）
+    pub read_timeout_secs: u64,
+    /// 单个请求从发出到读完响应的总时间上限（秒）
+    pub request_timeout_secs: u64,
+    /// 连接失败、超时或 5xx / 429 时的重试次数
+    pub retries: u32,
+    /// 第一次重试前的等待时间（毫秒），之后每次翻倍
+    pub retry_backoff_ms: u64,
+    /// 自定义 User-Agent，为空时使用 usefultools/<版本>
+    pub user_agent: Option<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            no_proxy: Vec::new(),
+            ca_certificates: None,
+            connect_timeout_secs: 10,
+            read_timeout_secs: 30,
+            request_timeout_secs: 30,
+            retries: 2,
+            retry_backoff_ms: 500,
+            user_agent: None,
+        }
+    }
+}
+
+impl NetworkConfig {
+    /// 按设置创建 HTTP 客户端
+    pub fn build_client(&self) -> Result<reqwest::Client, AppError> {
+        let user_agent = self
+            .user_agent
+            .clone()
+            .filter(|ua| !ua.trim().is_empty())
+            .unwrap_or_else(|| format!("usefultools/{}", env!("CARGO_PKG_VERSION")));
+
+        let mut builder = reqwest::Client::builder()
+            .user_agent(user_agent)
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs.max(1)))
+            .read_timeout(Duration::from_secs(self.read_timeout_secs.max(1)))
+            .timeout(Duration::from_secs(self.request_timeout_secs.max(1)));
+
+        if let Some(proxy) = self.proxy.as_deref().filter(|p| !p.trim().is_empty()) {
+            let proxy = reqwest::Proxy::all(proxy.trim())
+                .map_err(|e| AppError::invalid_input(format!("代理地址无效: {e}")))?
+                .no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(",")));
+            builder = builder.proxy(proxy);
+        }
+
+        if let Some(pem) = self
+            .ca_certificates
+            .as_deref()
+            .filter(|p| !p.trim().is_empty())
+        {
+            let certificates = reqwest::Certificate::from_pem_bundle(pem.as_bytes())
+                .map_err(|e| AppError::invalid_input(format!("根证书无效: {e}")))?;
+            if certificates.is_empty() {
+                return Err(AppError::invalid_input("根证书无效: 未找到 PEM 证书"));
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        builder
+            .build()
+            .map_err(|e| AppError::internal(format!("创建 HTTP 客户端失败: {e}")))
+    }
+
+    fn retry_backoff(&self) -> Duration {
+        Duration::from_millis(self.retry_backoff_ms)
+    }
+}
+
+/// 插件管理使用的 HTTP 客户端
+///
+/// 默认实现 [`HttpClients`] 按网络设置创建并复用 reqwest 客户端；直接使用
+/// `reqwest::Client` 时忽略网络设置（适合测试或由嵌入方统一配置客户端）。
+pub trait HttpClient: Send + Sync {
+    /// 取得与网络设置对应的客户端
+    fn client(&self, config: &NetworkConfig) -> Result<reqwest::Client, AppError>;
+}
+
+impl HttpClient for reqwest::Client {
+    fn client(&self, _config: &NetworkConfig) -> Result<reqwest::Client, AppError> {
+        Ok(self.clone())
+    }
+}
+
+/// 共享的 HTTP 客户端（Tauri 托管状态，由 PluginStore 共享），网络设置变化时重新创建
+#[derive(Clone, Default)]
+pub struct HttpClients(Arc<Mutex<Option<(NetworkConfig, reqwest::Client)>>>);
+
+impl HttpClients {
+    /// 取得与当前设置一致的客户端（内部共享连接池）
+    pub fn get(&self, config: &NetworkConfig) -> Result<reqwest::Client, AppError> {
+        let mut current = self
+            .0
+            .lock()
+            .map_err(|_| AppError::internal("HTTP 客户端状态异常"))?;
+        if let Some((built_with, client)) = current.as_ref() {
+            if built_with == config {
+                return Ok(client.clone());
+            }
+        }
+        let client = config.build_client()?;
+        *current = Some((config.clone(), client.clone()));
+        Ok(client)
+    }
+}
+
+impl HttpClient for HttpClients {
+    fn client(&self, config: &NetworkConfig) -> Result<reqwest::Client, AppError> {
+        self.get(config)
+    }
+}
+
+// ── 访问 registry 的 HTTP 客户端 ────────────────────────────
+
+/// 为 registry 请求自动附带对应凭据的 HTTP 客户端
+#[derive(Clone, Default)]
+pub struct RegistryClient {
+    http: reqwest::Client,
+    credentials: Arc<Credentials>,
+    retries: u32,
+    retry_backoff: Duration,
+}
+
+impl RegistryClient {
+    pub fn new(http: reqwest::Client, credentials: Credentials) -> Self {
+        Self {
+            http,
+            credentials: Arc::new(credentials),
+            retries: 0,
+            retry_backoff: Duration::ZERO,
+        }
+    }
+
+    /// 使用网络设置中的重试策略
+    pub fn with_retry(self, config: &NetworkConfig) -> Self {
+        Self {
+            retries: config.retries,
+            retry_backoff: config.retry_backoff(),
+            ..self
+        }
+    }
+
+    /// 构造 GET 请求，地址匹配某个 registry 的凭据时附带 Authorization 头
+    pub fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.http.get(url);
+        match self.credentials.authorization_for(url) {
+            Some(value) => request.header(reqwest::header::AUTHORIZATION, value),
+            None => request,
+        }
+    }
+
+    /// 发送请求；连接失败、超时或服务端暂时不可用时按指数退避重试
+    pub async fn send(
+        &self,
+        request: reqwest::RequestBuilder,
+    ) -> reqwest::Result<reqwest::Response> {
+        let mut attempt = 0;
+        loop {
+            let Some(current) = request.try_clone() else {
+                return request.send().await;
+            };
+            let retry = attempt < self.retries;
+            match current.send().await {
+                Ok(resp) if retry && is_retryable_status(resp.status()) => {}
+                Err(e) if retry && (e.is_connect() || e.is_timeout()) => {}
+                result => return result,
+            }
+            tokio::time::sleep(self.retry_backoff * 2u32.saturating_pow(attempt)).await;
+            attempt += 1;
+        }
+    }
+
+    /// 隐去错误信息中的凭据
+    pub fn redact(&self, error: AppError) -> AppError {
+        self.credentials.redact_error(error)
+    }
+}
+
+fn is_retryable_status(status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::REQUEST_TIMEOUT
+        || status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
@@ -0,0 +1,152 @@
+use crate::error::{AppError, ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+// ── 插件依赖解析 ────────────────────────────────────────────
+
+/// 依赖解析失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DependencyError {
+    /// 依赖循环，按依赖链顺序列出（首尾相同）
+    Cycle(Vec<String>),
+    /// 依赖的插件在注册表中不存在
+    Missing { plugin: String, dependency: String },
+}
+
+impl fmt::Display for DependencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DependencyError::Cycle(chain) => {
+                write!(f, "检测到插件依赖循环: {}", chain.join(" -> "))
+            }
+            DependencyError::Missing { plugin, dependency } => {
+                write!(f, "插件 {plugin} 依赖的 {dependency} 在注册表中不存在")
+            }
+        }
+    }
+}
+
+impl std::error::Error for DependencyError {}
+
+impl From<DependencyError> for AppError {
+    fn from(e: DependencyError) -> Self {
+        let error = AppError::new(ErrorCode::Dependency, e.to_string());
+        match e {
+            DependencyError::Missing { plugin, .. } => error.with_plugin(plugin),
+            DependencyError::Cycle(_) => error,
+        }
+    }
+}
+
+/// 计算安装 `root` 前需要先安装的插件
+///
+/// `deps_of` 为插件 id → 依赖 id 列表；已安装的插件视为已满足，不再展开。
+/// 返回结果按安装顺序排列（被依赖者在前），不包含 `root` 本身。
+pub fn resolve_install_order(
+    root: &str,
+    deps_of: &HashMap<String, Vec<String>>,
+    installed: &HashSet<String>,
+) -> Result<Vec<String>, DependencyError> {
+    let mut stack = Vec::new();
+    let mut done = HashSet::new();
+    let mut order = Vec::new();
+
+    visit(root, deps_of, installed, &mut stack, &mut done, &mut order)?;
+    order.retain(|id| id != root);
+    Ok(order)
+}
+
+fn visit(
+    id: &str,
+    deps_of: &HashMap<String, Vec<String>>,
+    installed: &HashSet<String>,
+    stack: &mut Vec<String>,
+    done: &mut HashSet<String>,
+    order: &mut Vec<String>,
+) -> Result<(), DependencyError> {
+    if done.contains(id) {
+        return Ok(());
+    }
+
+    if let Some(pos) = stack.iter().position(|s| s == id) {
+        let mut chain = stack[pos..].to_vec();
+        chain.push(id.to_string());
+        return Err(DependencyError::Cycle(chain));
+    }
+
+    // 已安装的依赖（root 除外）无需展开
+    if !stack.is_empty() && installed.contains(id) {
+        done.insert(id.to_string());
+        return Ok(());
+    }
+
+    let deps = deps_of.get(id).ok_or_else(|| DependencyError::Missing {
+        plugin: stack.last().cloned().unwrap_or_default(),
+        dependency: id.to_string(),
+    })?;
+
+    stack.push(id.to_string());
+    for dep in deps {
+        visit(dep, deps_of, installed, stack, done, order)?;
+    }
+    stack.pop();
+
+    done.insert(id.to_string());
+    order.push(id.to_string());
+    Ok(())
+}
+
+/// 找出直接或间接依赖 `target` 的插件，按卸载顺序排列（最外层的依赖者在前）
+pub fn collect_dependents(target: &str, deps_of: &HashMap<String, Vec<String>>) -> Vec<String> {
+    let mut result: Vec<String> = Vec::new();
+    let mut expanded = HashSet::new();
+    let mut queue = vec![target.to_string()];
+
+    while let Some(current) = queue.pop() {
+        let mut direct: Vec<&String> = deps_of
+            .iter()
+            .filter(|(id, deps)| deps.contains(&current) && *id != target)
+            .map(|(id, _)| id)
+            .collect();
+        direct.sort();
+
+        for id in direct {
+            // 已记录的依赖者移到末尾，保证它先于其依赖被卸载
+            result.retain(|r| r != id);
+            result.push(id.clone());
+            if expanded.insert(id.clone()) {
+                queue.push(id.clone());
+            }
+        }
+    }
+
+    result.reverse();
+    result
+}
+
+// ── 依赖关系图 ──────────────────────────────────────────────
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyNode {
+    pub id: String,
+    pub title: Option<String>,
+    pub version: Option<String>,
+    pub installed: bool,
+}
+
+/// `from` 依赖 `to`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyEdge {
+    pub from: String,
+    pub to: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DependencyGraph {
+    pub nodes: Vec<DependencyNode>,
+    pub edges: Vec<DependencyEdge>,
+}
@@ -0,0 +1,144 @@
+use super::InstalledPluginInfo;
+use crate::error::{AppError, ErrorCode};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+// ── 已安装插件索引 ──────────────────────────────────────────
+//
+// installed-plugins.json 保存所有已安装插件的信息，读取已安装列表时不再逐个扫描插件目录。
+// 修改插件目录的操作完成后同步更新索引。读改写由进程内互斥锁与锁文件上的系统文件锁保护，
+// 写入时先写临时文件再 rename，索引缺失时从磁盘重建，损坏的索引先移到 .corrupt 再重建。
+
+/// 索引文件（相对 plugins 目录）
+pub const INDEX_FILE: &str = "installed-plugins.json";
+/// 损坏的索引在重建前移到这里，保留原内容供排查
+pub const CORRUPT_INDEX_FILE: &str = "installed-plugins.json.corrupt";
+/// 跨进程加锁用的文件（锁随文件句柄关闭释放，文件本身一直保留）
+const LOCK_FILE: &str = "installed-plugins.lock";
+const INDEX_VERSION: u32 = 1;
+/// 等待锁的最长时间
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledIndex {
+    pub version: u32,
+    pub plugins: BTreeMap<String, InstalledPluginInfo>,
+}
+
+impl Default for InstalledIndex {
+    fn default() -> Self {
+        Self {
+            version: INDEX_VERSION,
+            plugins: BTreeMap::new(),
+        }
+    }
+}
+
+/// 同一进程内的命令先在这里排队，再竞争锁文件
+static LOCK: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+/// 持有期间其他命令不能修改索引，drop 时释放
+///
+/// 跨进程的部分是锁文件上的系统文件锁，持有者崩溃时由系统释放，不需要按时间判断锁是否失效。
+pub struct IndexLock {
+    _guard: tokio::sync::MutexGuard<'static, ()>,
+    _file: std::fs::File,
+}
+
+/// 获取索引锁
+pub async fn lock(plugins_dir: &Path) -> Result<IndexLock, AppError> {
+    let guard = LOCK.lock().await;
+    tokio::fs::create_dir_all(plugins_dir)
+        .await
+        .map_err(|e| AppError::io(format!("无法创建插件目录: {e}")))?;
+
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(plugins_dir.join(LOCK_FILE))
+        .map_err(|e| AppError::io(format!("无法创建插件索引锁: {e}")))?;
+    let started = Instant::now();
+    loop {
+        match file.try_lock() {
+            Ok(()) => {
+                return Ok(IndexLock {
+                    _guard: guard,
+                    _file: file,
+                })
+            }
+            Err(std::fs::TryLockError::WouldBlock) => {
+                if started.elapsed() > LOCK_TIMEOUT {
+                    return Err(AppError::new(
+                        ErrorCode::Busy,
+                        "等待插件索引锁超时，可能有其他操作正在进行",
+                    ));
+                }
+                tokio::time::sleep(Duration::from_millis(50)).await;
+            }
+            Err(std::fs::TryLockError::Error(e)) => {
+                return Err(AppError::io(format!("无法锁定插件索引: {e}")))
+            }
+        }
+    }
+}
+
+/// 读取索引；不存在或版本不符时返回 None（需要重建），无法读取或已损坏时返回错误
+pub async fn read(plugins_dir: &Path) -> Result<Option<InstalledIndex>, AppError> {
+    let content = match tokio::fs::read_to_string(plugins_dir.join(INDEX_FILE)).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(AppError::io(format!("读取插件索引失败: {e}"))),
+    };
+    parse(&content)
+}
+
+fn parse(content: &str) -> Result<Option<InstalledIndex>, AppError> {
+    match serde_json::from_str::<InstalledIndex>(content) {
+        Ok(index) if index.version == INDEX_VERSION => Ok(Some(index)),
+        Ok(_) => Ok(None),
+        Err(e) => Err(AppError::parse(format!("插件索引已损坏: {e}"))),
+    }
+}
+
+/// 原子写入索引（调用方需持有锁）
+pub async fn write(plugins_dir: &Path, index: &InstalledIndex) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(index)
+        .map_err(|e| AppError::internal(format!("序列化插件索引失败: {e}")))?;
+    let path = plugins_dir.join(INDEX_FILE);
+    let tmp = path.with_extension(format!("tmp-{}", super::now_ms()));
+    tokio::fs::write(&tmp, json)
+        .await
+        .map_err(|e| AppError::io(format!("写入插件索引失败: {e}")))?;
+    if let Err(e) = tokio::fs::rename(&tmp, &path).await {
+        let _ = tokio::fs::remove_file(&tmp).await;
+        return Err(AppError::io(format!("写入插件索引失败: {e}")));
+    }
+    Ok(())
+}
+
+/// 把无法读取的索引移到 CORRUPT_INDEX_FILE（替换上次留下的），之后才能重建（调用方需持有锁）
+pub async fn set_aside(plugins_dir: &Path) -> Result<(), AppError> {
+    tokio::fs::rename(
+        plugins_dir.join(INDEX_FILE),
+        plugins_dir.join(CORRUPT_INDEX_FILE),
+    )
+    .await
+    .map_err(|e| AppError::io(format!("移走损坏的插件索引失败: {e}")))
+}
+
+/// 之前移走的损坏索引无法读取的原因，没有移走过时返回 None
+pub async fn corrupt_reason(plugins_dir: &Path) -> Option<AppError> {
+    let content = tokio::fs::read_to_string(plugins_dir.join(CORRUPT_INDEX_FILE))
+        .await
+        .ok()?;
+    parse(&content).err()
+}
+
+/// 删除索引，下次读取时从磁盘重建
+pub fn invalidate(plugins_dir: &Path) {
+    let _ = std::fs::remove_file(plugins_dir.join(INDEX_FILE));
+}
@@ -0,0 +1,193 @@
+use crate::error::{AppError, ErrorCode};
+use base64::Engine;
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use std::fmt;
+
+// ── tarball 完整性校验 ──────────────────────────────────────
+
+/// 完整性校验失败的原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntegrityError {
+    /// registry 既没有返回 integrity 也没有返回 shasum
+    Missing,
+    /// integrity / shasum 字段格式不合法
+    Malformed(String),
+    /// integrity 中没有任何受支持的哈希算法
+    UnsupportedAlgorithm(String),
+    /// 哈希不匹配
+    Mismatch {
+        algorithm: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::Missing => write!(f, "registry 未提供 integrity 或 shasum，拒绝安装"),
+            IntegrityError::Malformed(value) => write!(f, "完整性字段格式不合法: {value}"),
+            IntegrityError::UnsupportedAlgorithm(value) => {
+                write!(f, "不支持的完整性哈希算法: {value}")
+            }
+            IntegrityError::Mismatch {
+                algorithm,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "tarball 完整性校验失败（{algorithm}）：期望 {expected}，实际 {actual}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for IntegrityError {}
+
+impl From<IntegrityError> for AppError {
+    fn from(e: IntegrityError) -> Self {
+        AppError::new(ErrorCode::Integrity, e.to_string())
+    }
+}
+
+/// SRI 支持的算法，按强度从高到低排列
+const SRI_ALGORITHMS: &[&str] = &["sha512", "sha384", "sha256", "sha1"];
+
+fn digest(algorithm: &str, data: &[u8]) -> Vec<u8> {
+    match algorithm {
+        "sha512" => Sha512::digest(data).to_vec(),
+        "sha384" => Sha384::digest(data).to_vec(),
+        "sha256" => Sha256::digest(data).to_vec(),
+        _ => Sha1::digest(data).to_vec(),
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// 计算数据的 SRI 值（`sha512-<base64>`，与 npm 的 dist.integrity 格式相同）
+pub fn sri(data: &[u8]) -> String {
+    format!(
+        "sha512-{}",
+        base64::engine::general_purpose::STANDARD.encode(digest("sha512", data))
+    )
+}
+
+/// 计算数据的 SHA-1（十六进制，与 npm 的 dist.shasum 格式相同）
+pub fn shasum(data: &[u8]) -> String {
+    to_hex(&Sha1::digest(data))
+}
+
+/// 按 SRI 规范（`<alg>-<base64>`，空格分隔多个）校验数据
+///
+/// 只使用其中最强的算法；同一算法出现多次时任意一个匹配即可。
+pub fn verify_sri(data: &[u8], integrity: &str) -> Result<(), IntegrityError> {
+    let mut hashes: Vec<(&str, &str)> = Vec::new();
+    for token in integrity.split_whitespace() {
+        // 忽略 SRI 选项（`?` 之后的部分）
+        let token = token.split('?').next().unwrap_or(token);
+        let (algorithm, value) = token
+            .split_once('-')
+            .ok_or_else(|| IntegrityError::Malformed(token.to_string()))?;
+        hashes.push((algorithm, value));
+    }
+
+    if hashes.is_empty() {
+        return Err(IntegrityError::Malformed(integrity.to_string()));
+    }
+
+    let algorithm = SRI_ALGORITHMS
+        .iter()
+        .find(|alg| hashes.iter().any(|(a, _)| a == *alg))
+        .ok_or_else(|| IntegrityError::UnsupportedAlgorithm(integrity.to_string()))?;
+
+    let actual = base64::engine::general_purpose::STANDARD.encode(digest(algorithm, data));
+    let candidates: Vec<&str> = hashes
+        .iter()
+        .filter(|(a, _)| a == algorithm)
+        .map(|(_, v)| *v)
+        .collect();
+
+    if candidates.iter().any(|expected| *expected == actual) {
+        Ok(())
+    } else {
+        Err(IntegrityError::Mismatch {
+            algorithm: algorithm.to_string(),
+            expected: candidates.join(" "),
+            actual,
+        })
+    }
+}
+
+/// 校验 npm 的 `dist.shasum`（十六进制 SHA-1）
+pub fn verify_shasum(data: &[u8], shasum: &str) -> Result<(), IntegrityError> {
+    let expected = shasum.trim().to_ascii_lowercase();
+    if expected.len() != 40 || !expected.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(IntegrityError::Malformed(shasum.to_string()));
+    }
+
+    let actual = to_hex(&Sha1::digest(data));
+    if actual == expected {
+        Ok(())
+    } else {
+        Err(IntegrityError::Mismatch {
+            algorithm: "sha1".to_string(),
+            expected,
+            actual,
+        })
+    }
+}
+
+/// 校验下载的 tarball：integrity 与 shasum 只要提供了就都必须匹配
+pub fn verify_tarball(
+    data: &[u8],
+    integrity: Option<&str>,
+    shasum: Option<&str>,
+) -> Result<(), IntegrityError> {
+    let integrity = integrity.filter(|s| !s.trim().is_empty());
+    let shasum = shasum.filter(|s| !s.trim().is_empty());
+
+    if integrity.is_none() && shasum.is_none() {
+        return Err(IntegrityError::Missing);
+    }
+
+    if let Some(integrity) = integrity {
+        verify_sri(data, integrity)?;
+    }
+    if let Some(shasum) = shasum {
+        verify_shasum(data, shasum)?;
+    }
+    Ok(())
+}
+
+/// 根据 integrity / shasum 生成内容寻址的缓存键（如 `sha512-<十六进制>`）
+///
+/// 取 integrity 中最强的受支持算法；没有 integrity 时退回 shasum。
+pub fn content_key(integrity: Option<&str>, shasum: Option<&str>) -> Option<String> {
+    if let Some(integrity) = integrity {
+        let hashes: Vec<(&str, &str)> = integrity
+            .split_whitespace()
+            .filter_map(|token| token.split('?').next()?.split_once('-'))
+            .collect();
+        let found = SRI_ALGORITHMS.iter().find_map(|alg| {
+            hashes
+                .iter()
+                .find(|(a, _)| a == alg)
+                .map(|(a, value)| (*a, *value))
+        });
+        if let Some((algorithm, value)) = found {
+            if let Ok(digest) = base64::engine::general_purpose::STANDARD.decode(value) {
+                return Some(format!("{algorithm}-{}", to_hex(&digest)));
+            }
+        }
+    }
+
+    let shasum = shasum?.trim().to_ascii_lowercase();
+    if shasum.len() == 40 && shasum.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(format!("sha1-{shasum}"))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,168 @@
+use super::PluginMeta;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+// ── 插件锁文件 ──────────────────────────────────────────────
+//
+// 记录已安装插件的精确版本、来源 registry 与 tarball 完整性，导入同一个锁文件即可
+// 得到完全相同的插件集合。导出时可以把 tarball 打包到锁文件旁的 tarballs 目录，
+// 导入时优先使用，无需访问 registry。
+
+/// 当前锁文件格式版本
+pub const LOCKFILE_VERSION: u32 = 1;
+/// 打包的 tarball 所在目录（相对锁文件所在目录）
+pub const TARBALL_DIR: &str = "tarballs";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Lockfile {
+    pub lockfile_version: u32,
+    pub plugins: Vec<LockedPlugin>,
+}
+
+/// 锁定的单个插件
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedPlugin {
+    pub id: String,
+    pub package_name: String,
+    /// 精确版本
+    pub version: String,
+    #[serde(default)]
+    pub registry: Option<String>,
+    /// tarball 的 SRI 哈希
+    #[serde(default)]
+    pub integrity: Option<String>,
+    /// 非 registry 的安装来源（如 "local file"）
+    #[serde(default)]
+    pub origin: Option<String>,
+    /// 打包的 tarball 路径（相对锁文件所在目录）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tarball: Option<String>,
+}
+
+impl LockedPlugin {
+    pub fn from_meta(meta: &PluginMeta) -> Self {
+        Self {
+            id: meta.id.clone(),
+            package_name: meta.package_name.clone(),
+            version: meta.version.clone(),
+            registry: meta.registry.clone(),
+            integrity: meta.integrity.clone(),
+            origin: meta.origin.clone(),
+            tarball: None,
+        }
+    }
+
+    /// 是否为同一份安装内容（任一方没有记录 integrity 时只比较包名与版本）
+    fn same_install(&self, other: &LockedPlugin) -> bool {
+        self.package_name == other.package_name
+            && self.version == other.version
+            && match (&self.integrity, &other.integrity) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    }
+}
+
+impl Lockfile {
+    /// 由已安装插件生成锁文件（按 id 排序）
+    pub fn from_installed<'a>(plugins: impl IntoIterator<Item = &'a PluginMeta>) -> Self {
+        let mut plugins: Vec<LockedPlugin> =
+            plugins.into_iter().map(LockedPlugin::from_meta).collect();
+        plugins.sort_by(|a, b| a.id.cmp(&b.id));
+        Self {
+            lockfile_version: LOCKFILE_VERSION,
+            plugins,
+        }
+    }
+
+    /// 解析并校验锁文件
+    pub fn parse(content: &str) -> Result<Self, AppError> {
+        let lockfile: Lockfile = serde_json::from_str(content)
+            .map_err(|e| AppError::parse(format!("解析锁文件失败: {e}")))?;
+        if lockfile.lockfile_version > LOCKFILE_VERSION {
+            return Err(AppError::invalid_input(format!(
+                "不支持的锁文件版本 {}（当前支持 {}）",
+                lockfile.lockfile_version, LOCKFILE_VERSION
+            )));
+        }
+
+        let mut seen = HashSet::new();
+        for plugin in &lockfile.plugins {
+            super::validate_plugin_id(&plugin.id)?;
+            if !seen.insert(plugin.id.as_str()) {
+                return Err(AppError::invalid_input(format!(
+                    "锁文件中插件 {} 重复",
+                    plugin.id
+                )));
+            }
+            if let Some(tarball) = &plugin.tarball {
+                if !super::tarball::is_safe_relative_path(tarball) {
+                    return Err(AppError::invalid_input(format!(
+                        "锁文件中包含不安全的 tarball 路径: {tarball}"
+                    )));
+                }
+                if plugin.integrity.is_none() {
+                    return Err(AppError::invalid_input(format!(
+                        "插件 {} 打包了 tarball 但缺少 integrity",
+                        plugin.id
+                    )));
+                }
+            }
+        }
+        Ok(lockfile)
+    }
+}
+
+// ── 差异 ────────────────────────────────────────────────────
+
+/// 版本或内容发生变化的插件
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct LockedChange {
+    pub id: String,
+    pub from: LockedPlugin,
+    pub to: LockedPlugin,
+}
+
+/// 当前安装状态与锁文件的差异
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct LockfileDiff {
+    /// 需要新安装的插件
+    pub added: Vec<LockedPlugin>,
+    /// 需要卸载的插件
+    pub removed: Vec<LockedPlugin>,
+    /// 需要重新安装为锁定版本的插件
+    pub changed: Vec<LockedChange>,
+}
+
+/// 比较当前安装的插件与目标锁文件
+pub fn diff(current: &[LockedPlugin], target: &[LockedPlugin]) -> LockfileDiff {
+    let current_by_id: HashMap<&str, &LockedPlugin> =
+        current.iter().map(|p| (p.id.as_str(), p)).collect();
+    let target_ids: HashSet<&str> = target.iter().map(|p| p.id.as_str()).collect();
+
+    let mut result = LockfileDiff::default();
+    for locked in target {
+        match current_by_id.get(locked.id.as_str()) {
+            None => result.added.push(locked.clone()),
+            Some(installed) if !installed.same_install(locked) => {
+                result.changed.push(LockedChange {
+                    id: locked.id.clone(),
+                    from: (*installed).clone(),
+                    to: locked.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    result.removed = current
+        .iter()
+        .filter(|p| !target_ids.contains(p.id.as_str()))
+        .cloned()
+        .collect();
+    result
+}
@@ -0,0 +1,505 @@
+use super::PluginJsonEntry;
+use crate::error::{AppError, ErrorCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::path::Path;
+
+// ── plugin.json 校验 ────────────────────────────────────────
+//
+// 先按 JSON 结构逐字段检查类型，再检查取值，每条诊断都带有 JSON 路径
+// （如 `$.plugins[1].bgColor`），避免 untagged 反序列化只能报出
+// "data did not match any variant"。
+//
+// 只有加载插件所依赖的问题才是错误（缺少必填字段或类型不对、id 不合法或重复、依赖、
+// bundle 路径）；标题、颜色、分类、权限等展示与声明上的问题只给出警告，
+// 以免已发布的旧插件因校验变严而无法安装。
+
+/// 应用支持的插件权限（与前端 PluginCapability 一致）
+pub const KNOWN_CAPABILITIES: &[&str] = &[
+    "fs:read",
+    "fs:write",
+    "http",
+    "dialog",
+    "clipboard",
+    "system:info",
+    "system:process",
+    "shell:execute",
+    "notification",
+];
+
+/// 推荐使用的分类，其他分类会给出警告
+pub const KNOWN_CATEGORIES: &[&str] = &[
+    "编码开发",
+    "文本处理",
+    "数据转换",
+    "加密安全",
+    "图片处理",
+    "网络工具",
+    "系统工具",
+    "效率工具",
+    "设计工具",
+    "其他",
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum Severity {
+    /// 插件无法安装
+    Error,
+    /// 可以安装，但很可能是写错了
+    Warning,
+}
+
+/// 单条诊断
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub severity: Severity,
+    /// JSON 路径（如 $.plugins[0].id）
+    pub path: String,
+    pub message: String,
+}
+
+/// 校验结果
+#[derive(Serialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ManifestReport {
+    /// 没有错误的条目
+    pub entries: Vec<PluginJsonEntry>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// entries 中各条目的 JSON 路径
+    #[serde(skip)]
+    paths: Vec<String>,
+}
+
+impl ManifestReport {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|d| d.severity == Severity::Error)
+    }
+
+    /// 警告（有警告时条目仍可使用）
+    pub fn warnings(&self) -> Vec<Diagnostic> {
+        self.diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Warning)
+            .cloned()
+            .collect()
+    }
+
+    /// 有错误时返回所有错误（每行一条，带 JSON 路径），全部诊断记在 context.diagnostics 中
+    pub fn into_result(self) -> Result<Vec<PluginJsonEntry>, AppError> {
+        if !self.has_errors() {
+            return Ok(self.entries);
+        }
+        let errors: Vec<String> = self
+            .diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| format!("{}: {}", d.path, d.message))
+            .collect();
+        Err(AppError::new(
+            ErrorCode::InvalidManifest,
+            format!("plugin.json 校验失败:\n{}", errors.join("\n")),
+        )
+        .with_diagnostics(self.diagnostics))
+    }
+
+    /// 条目及其 JSON 路径
+    pub fn entries_with_paths(&self) -> impl Iterator<Item = (&str, &PluginJsonEntry)> {
+        self.paths
+            .iter()
+            .map(String::as_str)
+            .zip(self.entries.iter())
+    }
+
+    pub fn error(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Error, path.into(), message.into());
+    }
+
+    fn warning(&mut self, path: impl Into<String>, message: impl Into<String>) {
+        self.push(Severity::Warning, path.into(), message.into());
+    }
+
+    fn push(&mut self, severity: Severity, path: String, message: String) {
+        self.diagnostics.push(Diagnostic {
+            severity,
+            path,
+            message,
+        });
+    }
+}
+
+/// 字段类型
+#[derive(Clone, Copy)]
+enum Kind {
+    String,
+    StringArray,
+}
+
+/// (字段名, 是否必填, 类型)
+const FIELDS: &[(&str, bool, Kind)] = &[
+    ("id", true, Kind::String),
+    ("version", true, Kind::String),
+    ("author", true, Kind::String),
+    ("homepage", false, Kind::String),
+    ("icon", true, Kind::String),
+    ("title", true, Kind::String),
+    ("subtitle", true, Kind::String),
+    ("description", true, Kind::String),
+    ("bgColor", true, Kind::String),
+    ("textColor", false, Kind::String),
+    ("categories", true, Kind::StringArray),
+    ("requires", false, Kind::StringArray),
+    ("dependencies", false, Kind::StringArray),
+    ("bundle", true, Kind::String),
+];
+
+/// 校验 plugin.json
+///
+/// `installed` 为已安装插件 id → 所属包名；与其他包中已安装插件 id 相同视为错误。
+pub fn validate(
+    content: &str,
+    package_name: &str,
+    installed: &HashMap<String, String>,
+) -> ManifestReport {
+    let mut report = ManifestReport::default();
+
+    let root: Value = match serde_json::from_str(content) {
+        Ok(root) => root,
+        Err(e) => {
+            report.error(
+                "$",
+                format!(
+                    "不是合法的 JSON（第 {} 行第 {} 列）: {e}",
+                    e.line(),
+                    e.column()
+                ),
+            );
+            return report;
+        }
+    };
+    let Some(object) = root.as_object() else {
+        report.error("$", "根节点必须是对象");
+        return report;
+    };
+
+    // 多工具包：{ "plugins": [...] }；否则整个对象是单个工具
+    let entries: Vec<(String, &Map<String, Value>)> = match object.get("plugins") {
+        Some(Value::Array(items)) => {
+            for key in object.keys().filter(|k| *k != "plugins") {
+                report.warning(
+                    format!("$.{key}"),
+                    "多工具包的根节点只应包含 plugins，该字段会被忽略",
+                );
+            }
+            if items.is_empty() {
+                report.error("$.plugins", "plugins 不能为空");
+            }
+            let mut entries = Vec::new();
+            for (i, item) in items.iter().enumerate() {
+                match item.as_object() {
+                    Some(entry) => entries.push((format!("$.plugins[{i}]"), entry)),
+                    None => report.error(format!("$.plugins[{i}]"), "插件条目必须是对象"),
+                }
+            }
+            entries
+        }
+        Some(_) => {
+            report.error("$.plugins", "plugins 必须是数组");
+            return report;
+        }
+        None => vec![("$".to_string(), object)],
+    };
+
+    let mut seen: HashMap<String, String> = HashMap::new();
+    for (path, entry) in entries {
+        let errors_before = error_count(&report);
+        check_entry(&mut report, &path, entry);
+
+        if let Some(id) = entry.get("id").and_then(Value::as_str) {
+            if let Some(first) = seen.get(id) {
+                report.error(
+                    format!("{path}.id"),
+                    format!("插件 id {id} 与 {first} 重复"),
+                );
+            } else {
+                seen.insert(id.to_string(), path.clone());
+            }
+            if let Some(other) = installed.get(id).filter(|p| *p != package_name) {
+                report.error(
+                    format!("{path}.id"),
+                    format!("插件 id {id} 已被已安装的包 {other} 使用"),
+                );
+            }
+        }
+
+        if error_count(&report) > errors_before {
+            continue;
+        }
+        // null 与缺省等价
+        let entry: Map<String, Value> = entry
+            .iter()
+            .filter(|(_, v)| !v.is_null())
+            .map(|(k, v)| (k.clone(), v.clone()))
+            .collect();
+        match serde_json::from_value::<PluginJsonEntry>(Value::Object(entry)) {
+            Ok(parsed) => {
+                report.entries.push(parsed);
+                report.paths.push(path);
+            }
+            Err(e) => report.error(path, format!("解析失败: {e}")),
+        }
+    }
+
+    report
+}
+
+/// 校验插件目录中的 plugin.json，并检查每个 bundle 文件是否存在
+pub async fn validate_dir(
+    dir: &Path,
+    package_name: &str,
+    installed: &HashMap<String, String>,
+) -> Result<ManifestReport, AppError> {
+    let plugin_json_path = dir.join("plugin.json");
+    if !plugin_json_path.exists() {
+        return Err(AppError::not_found(format!(
+            "未找到 plugin.json: {}",
+            plugin_json_path.display()
+        )));
+    }
+
+    let content = tokio::fs::read_to_string(&plugin_json_path)
+        .await
+        .map_err(|e| AppError::io(format!("读取 plugin.json 失败: {e}")))?;
+
+    let mut report = validate(&content, package_name, installed);
+    let missing: Vec<(String, String)> = report
+        .entries_with_paths()
+        .filter(|(_, entry)| !dir.join(&entry.bundle).is_file())
+        .map(|(path, entry)| {
+            (
+                format!("{path}.bundle"),
+                format!("bundle 文件不存在: {}", dir.join(&entry.bundle).display()),
+            )
+        })
+        .collect();
+    for (path, message) in missing {
+        report.error(path, message);
+    }
+    Ok(report)
+}
+
+fn error_count(report: &ManifestReport) -> usize {
+    report
+        .diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count()
+}
+
+/// 检查单个条目的字段类型与取值
+fn check_entry(report: &mut ManifestReport, path: &str, entry: &Map<String, Value>) {
+    for key in entry.keys() {
+        if FIELDS.iter().any(|(name, _, _)| name == key) {
+            continue;
+        }
+        let message = match suggest_field(key) {
+            Some(name) => format!("未知字段 {key}，是否应为 {name}？"),
+            None => format!("未知字段 {key}，该字段会被忽略"),
+        };
+        report.warning(format!("{path}.{key}"), message);
+    }
+
+    for (name, required, kind) in FIELDS {
+        let field_path = format!("{path}.{name}");
+        match (entry.get(*name), kind) {
+            (None, _) | (Some(Value::Null), _) => {
+                if *required {
+                    report.error(field_path, format!("缺少必填字段 {name}"));
+                }
+            }
+            (Some(Value::String(_)), Kind::String) => {}
+            (Some(Value::Array(items)), Kind::StringArray) => {
+                for (i, item) in items.iter().enumerate() {
+                    if !item.is_string() {
+                        report.error(format!("{field_path}[{i}]"), "必须是字符串");
+                    }
+                }
+            }
+            (Some(_), Kind::String) => report.error(field_path, "必须是字符串"),
+            (Some(_), Kind::StringArray) => report.error(field_path, "必须是字符串数组"),
+        }
+    }
+
+    let text = |name: &str| entry.get(name).and_then(Value::as_str);
+    // 字符串数组中的字符串及其下标（类型不对的元素已报告过，这里跳过但保留下标）
+    let list = |name: &str| -> Vec<(usize, &str)> {
+        entry
+            .get(name)
+            .and_then(Value::as_array)
+            .map(|items| {
+                items
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(i, item)| Some((i, item.as_str()?)))
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    if let Some(id) = text("id") {
+        if let Err(e) = super::validate_plugin_id(id) {
+            report.error(format!("{path}.id"), e.message);
+        }
+    }
+    if let Some(version) = text("version") {
+        if super::version::parse_version(version).is_none() {
+            report.warning(
+                format!("{path}.version"),
+                format!("{version} 不是合法的 semver 版本，将无法检查更新"),
+            );
+        }
+    }
+    if text("author").is_some_and(|a| a.trim().is_empty()) {
+        report.warning(format!("{path}.author"), "author 为空");
+    }
+    if let Some(icon) = text("icon") {
+        if icon.is_empty()
+            || !icon
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            report.warning(
+                format!("{path}.icon"),
+                format!("{icon:?} 不是 Material Icons 图标名（小写字母、数字与 _）"),
+            );
+        }
+    }
+    if text("title").is_some_and(|t| t.replace("<br/>", "").trim().is_empty()) {
+        report.warning(format!("{path}.title"), "title 为空");
+    }
+    for name in ["subtitle", "description"] {
+        if text(name).is_some_and(|t| t.trim().is_empty()) {
+            report.warning(format!("{path}.{name}"), format!("{name} 为空"));
+        }
+    }
+    if let Some(bg) = text("bgColor") {
+        if let Err(e) = check_color_classes(bg, "bg-") {
+            report.warning(format!("{path}.bgColor"), e);
+        }
+    }
+    if let Some(color) = text("textColor") {
+        if let Err(e) = check_color_classes(color, "text-") {
+            report.warning(format!("{path}.textColor"), e);
+        }
+    }
+
+    let categories = list("categories");
+    if entry.get("categories").is_some_and(Value::is_array) && categories.is_empty() {
+        report.warning(
+            format!("{path}.categories"),
+            "没有任何分类，工具不会出现在分类标签中",
+        );
+    }
+    for &(i, category) in &categories {
+        if category.trim().is_empty() {
+            report.warning(format!("{path}.categories[{i}]"), "分类为空");
+        } else if !KNOWN_CATEGORIES.contains(&category) {
+            report.warning(
+                format!("{path}.categories[{i}]"),
+                format!(
+                    "未知分类 {category}（可用: {}）",
+                    KNOWN_CATEGORIES.join("、")
+                ),
+            );
+        }
+    }
+
+    for (i, capability) in list("requires") {
+        if !KNOWN_CAPABILITIES.contains(&capability) {
+            report.warning(
+                format!("{path}.requires[{i}]"),
+                format!(
+                    "未知权限 {capability}（可用: {}）",
+                    KNOWN_CAPABILITIES.join(", ")
+                ),
+            );
+        }
+    }
+
+    for (i, dependency) in list("dependencies") {
+        let dep_path = format!("{path}.dependencies[{i}]");
+        if let Err(e) = super::validate_plugin_id(dependency) {
+            report.error(dep_path, e.message);
+        } else if text("id") == Some(dependency) {
+            report.error(dep_path, "插件不能依赖自身");
+        }
+    }
+
+    if let Some(bundle) = text("bundle") {
+        if !super::tarball::is_safe_relative_path(bundle) {
+            report.error(
+                format!("{path}.bundle"),
+                format!("bundle 路径不合法: {bundle}"),
+            );
+        } else if !bundle.ends_with(".mjs") && !bundle.ends_with(".js") {
+            report.warning(format!("{path}.bundle"), "bundle 应为 .mjs 或 .js 文件");
+        }
+    }
+}
+
+/// 颜色字段是 Tailwind class 列表，至少包含一个 `prefix` 开头的 class
+fn check_color_classes(value: &str, prefix: &str) -> Result<(), String> {
+    let valid_chars = value
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c.is_ascii_whitespace() || "-_:/[]#.%()".contains(c));
+    if !valid_chars {
+        return Err(format!("{value:?} 包含不允许的字符"));
+    }
+    if !value.split_whitespace().any(|class| {
+        class
+            .rsplit(':')
+            .next()
+            .is_some_and(|c| c.starts_with(prefix))
+    }) {
+        return Err(format!("{value:?} 中没有 {prefix}* 颜色 class"));
+    }
+    Ok(())
+}
+
+/// 为拼错的字段名找最接近的已知字段（编辑距离不超过 2）
+fn suggest_field(key: &str) -> Option<&'static str> {
+    FIELDS
+        .iter()
+        .map(|(name, _, _)| {
+            (
+                *name,
+                edit_distance(&key.to_lowercase(), &name.to_lowercase()),
+            )
+        })
+        .filter(|(_, distance)| *distance <= 2)
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(name, _)| name)
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                prev
+            } else {
+                1 + prev.min(row[j]).min(row[j + 1])
+            };
+            prev = current;
+        }
+    }
+    row[b.len()]
+}
@@ -0,0 +1,307 @@
+use super::{integrity, manifest, tarball};
+use crate::error::{AppError, ErrorCode};
+use base64::Engine;
+use serde::Serialize;
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::Path;
+
+// ── 插件打包与发布 ──────────────────────────────────────────
+//
+// 供插件作者使用（usefultools-plugin 命令行工具）：把插件目录按 npm pack 的布局
+// 打成 tarball（所有文件位于 `package/` 下），并按 npm publish 协议发布到
+// Verdaccio 等 registry。打包结果与应用安装时使用同一套校验。
+
+/// 打包时固定的文件修改时间（与 npm pack 相同），保证同样的内容得到同样的 tarball
+const PACK_MTIME: u64 = 499_162_500;
+
+/// 随包附带的说明文件（按文件名前缀匹配，不区分大小写）
+const EXTRA_FILES: &[&str] = &["readme", "license", "licence", "changelog"];
+
+/// 一个打包好的插件包
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    /// tarball 字节数
+    pub size: usize,
+    pub integrity: String,
+    pub shasum: String,
+    /// tarball 中的文件及其大小
+    pub files: Vec<PackedFile>,
+    /// plugin.json 的校验结果（含 bundle 是否存在）
+    pub manifest: manifest::ManifestReport,
+    #[serde(skip)]
+    pub package_json: Map<String, Value>,
+    #[serde(skip)]
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PackedFile {
+    pub path: String,
+    pub size: u64,
+}
+
+impl Package {
+    /// 解析并检查 tarball，检查方式与应用安装时一致
+    pub fn from_tarball(bytes: Vec<u8>) -> Result<Self, AppError> {
+        let files: Vec<PackedFile> = tarball::list_entries(&bytes)?
+            .into_iter()
+            .map(|(path, size)| PackedFile { path, size })
+            .collect();
+
+        let mut extracted =
+            tarball::extract_files(&bytes, &["package/package.json", "package/plugin.json"])?;
+        let package_json = extracted
+            .remove("package/package.json")
+            .ok_or_else(|| missing_file("package/package.json"))?;
+        let package_json = parse_package_json(&String::from_utf8_lossy(&package_json))?;
+        let (name, version) = name_and_version(&package_json)?;
+
+        let plugin_json = extracted
+            .remove("package/plugin.json")
+            .ok_or_else(|| missing_file("package/plugin.json"))?;
+        let mut report = manifest::validate(
+            &String::from_utf8_lossy(&plugin_json),
+            &name,
+            &HashMap::new(),
+        );
+        let missing: Vec<(String, String)> = report
+            .entries_with_paths()
+            .filter(|(_, entry)| {
+                let target = format!("package/{}", entry.bundle);
+                !files.iter().any(|f| f.path == target)
+            })
+            .map(|(path, entry)| {
+                (
+                    format!("{path}.bundle"),
+                    format!("tarball 中缺少 bundle 文件: package/{}", entry.bundle),
+                )
+            })
+            .collect();
+        for (path, message) in missing {
+            report.error(path, message);
+        }
+
+        Ok(Self {
+            name,
+            version,
+            size: bytes.len(),
+            integrity: integrity::sri(&bytes),
+            shasum: integrity::shasum(&bytes),
+            files,
+            manifest: report,
+            package_json,
+            bytes,
+        })
+    }
+
+    /// npm pack 的输出文件名（`@scope/name` → `scope-name-1.0.0.tgz`）
+    pub fn file_name(&self) -> String {
+        self.tgz_name(&self.name.trim_start_matches('@').replace('/', "-"))
+    }
+
+    /// registry 上的 tarball 文件名，不含 scope（`@scope/name` → `name-1.0.0.tgz`）
+    pub fn registry_file_name(&self) -> String {
+        let name = self.name.rsplit('/').next().unwrap_or(&self.name);
+        self.tgz_name(name)
+    }
+
+    fn tgz_name(&self, name: &str) -> String {
+        format!("{name}-{}.tgz", self.version)
+    }
+}
+
+fn missing_file(path: &str) -> AppError {
+    AppError::new(ErrorCode::InvalidPackage, format!("tarball 中缺少 {path}"))
+}
+
+fn invalid_package_json(message: impl Into<String>) -> AppError {
+    AppError::new(ErrorCode::InvalidManifest, message)
+}
+
+fn parse_package_json(content: &str) -> Result<Map<String, Value>, AppError> {
+    match serde_json::from_str::<Value>(content) {
+        Ok(Value::Object(map)) => Ok(map),
+        Ok(_) => Err(invalid_package_json("package.json 必须是一个对象")),
+        Err(e) => Err(AppError::parse(format!("解析 package.json 失败: {e}"))),
+    }
+}
+
+fn name_and_version(package_json: &Map<String, Value>) -> Result<(String, String), AppError> {
+    let field = |key: &str| {
+        package_json
+            .get(key)
+            .and_then(Value::as_str)
+            .filter(|s| !s.trim().is_empty())
+            .map(str::to_string)
+            .ok_or_else(|| invalid_package_json(format!("package.json 缺少 {key}")))
+    };
+    let name = field("name")?;
+    let version = field("version")?;
+    if super::version::parse_version(&version).is_none() {
+        return Err(invalid_package_json(format!(
+            "package.json 的 version 不是合法的语义化版本: {version}"
+        )));
+    }
+    Ok((name, version))
+}
+
+/// 读取插件目录的 package.json
+pub async fn read_package_json(dir: &Path) -> Result<Map<String, Value>, AppError> {
+    let path = dir.join("package.json");
+    let content = tokio::fs::read_to_string(&path)
+        .await
+        .map_err(|e| AppError::io(format!("读取 {} 失败: {e}", path.display())))?;
+    parse_package_json(&content)
+}
+
+// ── 打包 ────────────────────────────────────────────────────
+
+/// 把插件目录打包为可安装的 tarball
+///
+/// 包含 package.json、plugin.json、所有 bundle 以及 README / LICENSE / CHANGELOG。
+/// plugin.json 有错误时拒绝打包。
+pub async fn pack(dir: &Path) -> Result<Package, AppError> {
+    let package_json = read_package_json(dir).await?;
+    let (name, _) = name_and_version(&package_json)?;
+
+    let report = manifest::validate_dir(dir, &name, &HashMap::new()).await?;
+    if report.has_errors() {
+        return Err(report.into_result().unwrap_err());
+    }
+
+    let mut files = vec!["package.json".to_string(), "plugin.json".to_string()];
+    for entry in &report.entries {
+        if !files.contains(&entry.bundle) {
+            files.push(entry.bundle.clone());
+        }
+    }
+    let mut read_dir = tokio::fs::read_dir(dir)
+        .await
+        .map_err(|e| AppError::io(format!("读取插件目录失败: {e}")))?;
+    let mut extras = Vec::new();
+    while let Ok(Some(entry)) = read_dir.next_entry().await {
+        let file_name = entry.file_name().to_string_lossy().to_string();
+        let lower = file_name.to_lowercase();
+        let is_file = entry.file_type().await.is_ok_and(|t| t.is_file());
+        if is_file && EXTRA_FILES.iter().any(|prefix| lower.starts_with(prefix)) {
+            extras.push(file_name);
+        }
+    }
+    extras.sort();
+    files.extend(extras);
+
+    let mut contents = Vec::with_capacity(files.len());
+    for file in files {
+        let data = tokio::fs::read(dir.join(&file))
+            .await
+            .map_err(|e| AppError::io(format!("读取 {file} 失败: {e}")))?;
+        contents.push((file, data));
+    }
+
+    let bytes = build_tarball(&contents).map_err(|e| AppError::io(format!("打包失败: {e}")))?;
+    Package::from_tarball(bytes)
+}
+
+fn build_tarball(files: &[(String, Vec<u8>)]) -> std::io::Result<Vec<u8>> {
+    let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    let mut builder = tar::Builder::new(encoder);
+    for (path, data) in files {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(data.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(PACK_MTIME);
+        header.set_entry_type(tar::EntryType::Regular);
+        builder.append_data(&mut header, format!("package/{path}"), data.as_slice())?;
+    }
+    let mut encoder = builder.into_inner()?;
+    encoder.flush()?;
+    encoder.finish()
+}
+
+// ── 发布 ────────────────────────────────────────────────────
+
+/// 按 npm publish 协议把包发布到 registry，返回 tarball 的下载地址
+pub async fn publish(
+    client: &reqwest::Client,
+    registry: &str,
+    package: &Package,
+    tag: &str,
+    token: Option<&str>,
+) -> Result<String, AppError> {
+    if package.manifest.has_errors() {
+        return Err(package.manifest.clone().into_result().unwrap_err());
+    }
+
+    // scoped 包的 / 需要转义：PUT /@scope%2fname，tarball 位于 /@scope%2fname/-/name-1.0.0.tgz
+    let url = format!(
+        "{}/{}",
+        registry.trim_end_matches('/'),
+        package.name.replace('/', "%2f")
+    );
+    let file_name = package.registry_file_name();
+    let tarball_url = format!("{url}/-/{file_name}");
+
+    let mut version = package.package_json.clone();
+    version.insert(
+        "_id".into(),
+        json!(format!("{}@{}", package.name, package.version)),
+    );
+    version.insert(
+        "dist".into(),
+        json!({
+            "integrity": package.integrity,
+            "shasum": package.shasum,
+            "tarball": tarball_url,
+        }),
+    );
+
+    let body = json!({
+        "_id": package.name,
+        "name": package.name,
+        "description": package.package_json.get("description").cloned().unwrap_or(Value::Null),
+        "dist-tags": { tag: package.version },
+        "versions": { package.version.clone(): version },
+        "_attachments": {
+            file_name: {
+                "content_type": "application/octet-stream",
+                "data": base64::engine::general_purpose::STANDARD.encode(&package.bytes),
+                "length": package.bytes.len(),
+            }
+        },
+    });
+
+    let mut request = client.put(&url).json(&body);
+    if let Some(token) = token {
+        request = request.bearer_auth(token);
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| AppError::network(format!("连接 registry 失败: {e}")).with_url(&url))?;
+
+    let status = response.status();
+    if status.is_success() {
+        return Ok(tarball_url);
+    }
+    let text = response.text().await.unwrap_or_default();
+    let detail: String = text.chars().take(500).collect();
+    let error = match status.as_u16() {
+        401 => AppError::http(401, "registry 拒绝发布：未登录或 token 无效"),
+        code @ (403 | 409) => AppError::new(
+            ErrorCode::Http,
+            format!(
+                "registry 拒绝发布 {}@{}（版本可能已存在）: {detail}",
+                package.name, package.version
+            ),
+        )
+        .with_status(code),
+        code => AppError::http(code, format!("发布失败 (HTTP {status}): {detail}")),
+    };
+    Err(error.with_package(&package.name).with_url(url))
+}
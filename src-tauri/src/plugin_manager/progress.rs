@@ -0,0 +1,258 @@
+use super::store::{PluginStore, ProgressSink};
+use crate::error::{AppError, ErrorCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+// ── 安装进度与取消 ──────────────────────────────────────────
+
+/// 安装进度事件名
+pub const INSTALL_PROGRESS_EVENT: &str = "plugin-install-progress";
+
+/// 两次下载进度事件之间至少间隔的字节数
+const PROGRESS_STEP: u64 = 64 * 1024;
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum InstallPhase {
+    /// 解析包详情与版本
+    Resolve,
+    /// 下载 tarball
+    Download,
+    /// 校验完整性
+    Verify,
+    /// 解压 bundle
+    Extract,
+    /// 写入插件目录
+    Write,
+}
+
+/// 安装进度事件负载
+#[derive(Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct InstallProgress {
+    /// 用户发起安装的插件 id（用于取消）
+    pub task_id: String,
+    /// 当前正在处理的插件 id（安装依赖时与 task_id 不同）
+    pub plugin_id: String,
+    pub phase: InstallPhase,
+    pub received: u64,
+    pub total: Option<u64>,
+    /// 不影响安装的失败（如 tarball 未能写入缓存）
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<AppError>,
+}
+
+/// 取消令牌
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<(AtomicBool, tokio::sync::Notify)>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0 .0.store(true, Ordering::SeqCst);
+        self.0 .1.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0 .0.load(Ordering::SeqCst)
+    }
+
+    /// 等待直到被取消
+    pub async fn cancelled(&self) {
+        while !self.is_cancelled() {
+            let notified = self.0 .1.notified();
+            if self.is_cancelled() {
+                break;
+            }
+            notified.await;
+        }
+    }
+}
+
+/// 正在进行的安装任务（Tauri 托管状态，由 PluginStore 共享）
+#[derive(Clone, Default)]
+pub struct InstallTasks(Arc<Mutex<HashMap<String, CancelToken>>>);
+
+impl InstallTasks {
+    /// 登记一个安装任务，同一插件同时只能有一个任务
+    pub fn register(&self, task_id: &str) -> Result<TaskGuard, AppError> {
+        self.register_many(&[task_id.to_string()])
+    }
+
+    /// 批量登记共用同一取消令牌的任务，取消其中任意一个即取消整批
+    pub fn register_many(&self, task_ids: &[String]) -> Result<TaskGuard, AppError> {
+        let mut tasks = self
+            .0
+            .lock()
+            .map_err(|_| AppError::internal("安装任务状态异常"))?;
+        if let Some(busy) = task_ids.iter().find(|id| tasks.contains_key(*id)) {
+            return Err(
+                AppError::new(ErrorCode::Busy, format!("插件 {busy} 正在安装中")).with_plugin(busy),
+            );
+        }
+        let token = CancelToken::default();
+        for task_id in task_ids {
+            tasks.insert(task_id.clone(), token.clone());
+        }
+        Ok(TaskGuard {
+            tasks: self.clone(),
+            task_ids: task_ids.to_vec(),
+            token,
+        })
+    }
+
+    /// 取消任务，任务不存在时返回 false
+    pub fn cancel(&self, task_id: &str) -> bool {
+        match self.0.lock() {
+            Ok(tasks) => match tasks.get(task_id) {
+                Some(token) => {
+                    token.cancel();
+                    true
+                }
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+}
+
+/// 任务结束（包括出错）时自动注销
+pub struct TaskGuard {
+    tasks: InstallTasks,
+    task_ids: Vec<String>,
+    token: CancelToken,
+}
+
+impl TaskGuard {
+    pub fn task(&self, store: &PluginStore) -> InstallTask {
+        self.task_for(store, &self.task_ids[0])
+    }
+
+    /// 批量任务中某个插件的上下文
+    pub fn task_for(&self, store: &PluginStore, task_id: &str) -> InstallTask {
+        InstallTask {
+            progress: store.progress(),
+            task_id: task_id.to_string(),
+            plugin_id: task_id.to_string(),
+            token: self.token.clone(),
+        }
+    }
+}
+
+impl Drop for TaskGuard {
+    fn drop(&mut self) {
+        if let Ok(mut tasks) = self.tasks.0.lock() {
+            for task_id in &self.task_ids {
+                tasks.remove(task_id);
+            }
+        }
+    }
+}
+
+/// 单次安装的上下文：报告进度并检查取消
+#[derive(Clone)]
+pub struct InstallTask {
+    progress: Option<ProgressSink>,
+    task_id: String,
+    plugin_id: String,
+    token: CancelToken,
+}
+
+impl InstallTask {
+    /// 同一任务中安装另一个插件（依赖）
+    pub fn for_plugin(&self, plugin_id: &str) -> InstallTask {
+        InstallTask {
+            plugin_id: plugin_id.to_string(),
+            ..self.clone()
+        }
+    }
+
+    pub fn token(&self) -> &CancelToken {
+        &self.token
+    }
+
+    pub fn cancelled_error(&self) -> AppError {
+        AppError::new(
+            ErrorCode::Cancelled,
+            format!("插件 {} 的安装已取消", self.task_id),
+        )
+        .with_plugin(&self.task_id)
+    }
+
+    /// 已取消时返回错误
+    pub fn check(&self) -> Result<(), AppError> {
+        if self.token.is_cancelled() {
+            Err(self.cancelled_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// 进入新阶段（会先检查是否已取消）
+    pub fn phase(&self, phase: InstallPhase) -> Result<(), AppError> {
+        self.check()?;
+        self.emit(phase, 0, None);
+        Ok(())
+    }
+
+    pub fn emit(&self, phase: InstallPhase, received: u64, total: Option<u64>) {
+        self.send(phase, received, total, None);
+    }
+
+    /// 报告不影响安装的失败，安装照常继续
+    pub fn warn(&self, phase: InstallPhase, warning: AppError) {
+        self.send(phase, 0, None, Some(warning.with_plugin(&self.plugin_id)));
+    }
+
+    fn send(
+        &self,
+        phase: InstallPhase,
+        received: u64,
+        total: Option<u64>,
+        warning: Option<AppError>,
+    ) {
+        if let Some(progress) = &self.progress {
+            progress(&InstallProgress {
+                task_id: self.task_id.clone(),
+                plugin_id: self.plugin_id.clone(),
+                phase,
+                received,
+                total,
+                warning,
+            });
+        }
+    }
+}
+
+/// 下载进度节流
+pub struct DownloadProgress<'a> {
+    task: &'a InstallTask,
+    total: Option<u64>,
+    received: u64,
+    last_emitted: u64,
+}
+
+impl<'a> DownloadProgress<'a> {
+    pub fn new(task: &'a InstallTask, total: Option<u64>) -> Self {
+        Self {
+            task,
+            total,
+            received: 0,
+            last_emitted: 0,
+        }
+    }
+
+    pub fn advance(&mut self, len: u64) {
+        self.received += len;
+        if self.received - self.last_emitted >= PROGRESS_STEP {
+            self.flush();
+        }
+    }
+
+    pub fn flush(&mut self) {
+        self.last_emitted = self.received;
+        self.task
+            .emit(InstallPhase::Download, self.received, self.total);
+    }
+}
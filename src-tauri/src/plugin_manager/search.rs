@@ -0,0 +1,92 @@
+use super::client::RegistryClient;
+use crate::error::AppError;
+use std::collections::HashSet;
+
+// ── npm search 分页 ─────────────────────────────────────────
+
+/// 每页请求的结果数（npm 允许的最大值为 250）
+pub const PAGE_SIZE: usize = 250;
+/// 单次搜索最多收集的包数量，防止 registry 返回异常的 total 时无限翻页
+pub const MAX_RESULTS: usize = 5_000;
+
+/// 一次分页搜索的结果
+#[derive(Debug, Default)]
+pub struct SearchResults {
+    /// 去重后的包名
+    pub names: Vec<String>,
+    /// 之后的页获取失败时的原因，此时 names 只是部分结果
+    pub incomplete: Option<AppError>,
+}
+
+/// 分页执行 npm search（GET /-/v1/search），返回去重后的包名列表
+///
+/// 按响应中的 `total` 翻页，直到取完、遇到空页或达到 MAX_RESULTS。
+/// 第一页失败时返回错误；之后的页失败时返回已取得的结果并在 `incomplete` 中带上原因。
+pub async fn search_all(
+    client: &RegistryClient,
+    registry: &str,
+    query: &str,
+) -> Result<SearchResults, AppError> {
+    let mut seen = HashSet::new();
+    let mut names = Vec::new();
+    let mut incomplete = None;
+    let mut from = 0usize;
+
+    loop {
+        let page = match search_page(client, registry, query, from).await {
+            Ok(page) => page,
+            Err(e) if from == 0 => return Err(e),
+            Err(e) => {
+                incomplete = Some(e.prefixed(format!("npm search 第 {from} 条之后的结果获取失败")));
+                break;
+            }
+        };
+
+        let count = page.objects.len();
+        for object in page.objects {
+            if names.len() >= MAX_RESULTS {
+                break;
+            }
+            if seen.insert(object.package.name.clone()) {
+                names.push(object.package.name);
+            }
+        }
+
+        from += count;
+        let total = page.total.map(|t| t as usize).unwrap_or(0);
+        if count == 0 || from >= total || from >= MAX_RESULTS || names.len() >= MAX_RESULTS {
+            break;
+        }
+    }
+
+    Ok(SearchResults { names, incomplete })
+}
+
+async fn search_page(
+    client: &RegistryClient,
+    registry: &str,
+    query: &str,
+    from: usize,
+) -> Result<super::NpmSearchResponse, AppError> {
+    let url = format!("{}/-/v1/search", registry);
+    let request = client.get(&url).query(&[
+        ("text", query.to_string()),
+        ("size", PAGE_SIZE.to_string()),
+        ("from", from.to_string()),
+    ]);
+    let resp = client
+        .send(request)
+        .await
+        .map_err(|e| AppError::network(format!("npm search 请求失败: {e}")).with_url(&url))?;
+
+    let status = resp.status().as_u16();
+    if !resp.status().is_success() {
+        return Err(
+            AppError::http(status, format!("npm search 请求失败: HTTP {status}")).with_url(&url),
+        );
+    }
+
+    resp.json()
+        .await
+        .map_err(|e| AppError::parse(format!("解析 npm search 结果失败: {e}")).with_url(&url))
+}
@@ -0,0 +1,222 @@
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+// ── 原子安装：暂存目录与崩溃恢复 ────────────────────────────
+//
+// 安装 / 更新时先把完整的插件目录写入 `.staging/<id>-<时间戳>`，
+// 再通过两次 rename 换入：`<id>` → `.old/<id>`，暂存目录 → `<id>`。
+// 换入成功后才删除旧版本，任何一步中断都可以在下次启动时恢复。
+
+/// 暂存目录（安装中的插件）
+pub const STAGING_DIR: &str = ".staging";
+/// 换入期间保存的旧版本
+pub const OLD_DIR: &str = ".old";
+/// 暂存目录中写入的标记文件，随暂存目录换入，表示插件目录由应用创建
+pub const INSTALL_MARKER: &str = ".usefultools-install";
+/// 上次启动时的恢复结果，前端读取后删除
+const REPORT_FILE: &str = "recovery-report.json";
+
+/// 插件目录是否为完整安装（bundle.mjs 与 meta.json 都存在）
+pub fn is_complete(plugin_dir: &Path) -> bool {
+    plugin_dir.join("bundle.mjs").is_file() && plugin_dir.join("meta.json").is_file()
+}
+
+/// 创建一个只含安装标记的暂存目录
+pub async fn create(plugins_dir: &Path, plugin_id: &str) -> Result<PathBuf, AppError> {
+    let staging = plugins_dir
+        .join(STAGING_DIR)
+        .join(format!("{}-{}", plugin_id, super::now_ms()));
+    tokio::fs::create_dir_all(&staging)
+        .await
+        .map_err(|e| AppError::io(format!("无法创建暂存目录: {e}")))?;
+    tokio::fs::write(staging.join(INSTALL_MARKER), b"")
+        .await
+        .map_err(|e| AppError::io(format!("写入安装标记失败: {e}")))?;
+    Ok(staging)
+}
+
+/// 丢弃暂存目录
+pub async fn discard(staging: &Path) {
+    let _ = tokio::fs::remove_dir_all(staging).await;
+}
+
+/// 将暂存目录换入为正式的插件目录，旧版本保留到换入成功后才删除
+pub async fn commit(plugins_dir: &Path, plugin_id: &str, staging: &Path) -> Result<(), AppError> {
+    let target = plugins_dir.join(plugin_id);
+    let old_root = plugins_dir.join(OLD_DIR);
+    let old = old_root.join(plugin_id);
+
+    // 上次换入中断的残留：正式目录缺失时先恢复，否则直接清理
+    if old.exists() {
+        if target.exists() {
+            tokio::fs::remove_dir_all(&old)
+                .await
+                .map_err(|e| AppError::io(format!("清理旧版本失败: {e}")))?;
+        } else {
+            tokio::fs::rename(&old, &target)
+                .await
+                .map_err(|e| AppError::io(format!("恢复旧版本失败: {e}")))?;
+        }
+    }
+
+    if target.exists() {
+        tokio::fs::create_dir_all(&old_root)
+            .await
+            .map_err(|e| AppError::io(format!("无法创建旧版本目录: {e}")))?;
+        tokio::fs::rename(&target, &old)
+            .await
+            .map_err(|e| AppError::io(format!("移出旧版本失败: {e}")))?;
+    }
+
+    if let Err(e) = tokio::fs::rename(staging, &target).await {
+        // 换入失败，放回旧版本
+        if old.exists() {
+            let _ = tokio::fs::rename(&old, &target).await;
+        }
+        return Err(AppError::io(format!("换入新版本失败: {e}")));
+    }
+
+    if old.exists() {
+        let _ = tokio::fs::remove_dir_all(&old).await;
+    }
+    Ok(())
+}
+
+/// 暂存目录准备完成后换入；准备或换入失败时丢弃暂存目录
+pub async fn finish(
+    plugins_dir: &Path,
+    plugin_id: &str,
+    staging: &Path,
+    prepared: Result<(), AppError>,
+) -> Result<(), AppError> {
+    let result = match prepared {
+        Ok(()) => commit(plugins_dir, plugin_id, staging).await,
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        discard(staging).await;
+    }
+    result
+}
+
+/// 把当前插件目录中的文件复制到暂存目录（不存在的文件忽略）
+pub async fn carry_over(
+    plugin_dir: &Path,
+    staging: &Path,
+    files: &[(&str, &str)],
+) -> Result<(), AppError> {
+    for (from, to) in files {
+        let source = plugin_dir.join(from);
+        if !source.is_file() {
+            continue;
+        }
+        tokio::fs::copy(&source, staging.join(to))
+            .await
+            .map_err(|e| AppError::io(format!("复制 {from} 失败: {e}")))?;
+    }
+    Ok(())
+}
+
+// ── 启动时恢复 ──────────────────────────────────────────────
+
+/// 恢复结果
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct RecoveryReport {
+    /// 恢复为上一个完整版本的插件
+    pub restored: Vec<String>,
+    /// 无法恢复、已删除的不完整插件目录
+    pub removed: Vec<String>,
+    /// 清理掉的暂存目录数量
+    pub discarded_staging: usize,
+}
+
+impl RecoveryReport {
+    pub fn is_empty(&self) -> bool {
+        self.restored.is_empty() && self.removed.is_empty() && self.discarded_staging == 0
+    }
+}
+
+/// 清理或完成中断的安装（同步执行，应在应用启动、处理任何 command 之前调用）
+pub fn recover(plugins_dir: &Path) -> RecoveryReport {
+    let mut report = RecoveryReport::default();
+
+    // 1. 换入中断：正式目录完整则删除旧版本，否则把旧版本放回去
+    let old_root = plugins_dir.join(OLD_DIR);
+    for (id, old) in list_dirs(&old_root) {
+        let target = plugins_dir.join(&id);
+        if is_complete(&target) {
+            let _ = std::fs::remove_dir_all(&old);
+            continue;
+        }
+        if target.exists() {
+            let _ = std::fs::remove_dir_all(&target);
+        }
+        if std::fs::rename(&old, &target).is_ok() {
+            report.restored.push(id);
+        }
+    }
+    let _ = std::fs::remove_dir_all(&old_root);
+
+    // 2. 未完成的暂存目录直接丢弃
+    let staging_root = plugins_dir.join(STAGING_DIR);
+    report.discarded_staging = list_dirs(&staging_root).len();
+    let _ = std::fs::remove_dir_all(&staging_root);
+
+    // 3. 不完整的插件目录：有备份则回退到备份；带安装标记（由应用创建）且无法恢复的删除；
+    //    其余目录不是应用创建的，保持原样，由修复索引时报告
+    for (id, dir) in list_dirs(plugins_dir) {
+        if id.starts_with('.') || is_complete(&dir) {
+            continue;
+        }
+        let bundle_backup = dir.join("bundle.mjs.bak");
+        let meta_backup = dir.join("meta.json.bak");
+        if bundle_backup.is_file()
+            && meta_backup.is_file()
+            && std::fs::rename(&bundle_backup, dir.join("bundle.mjs")).is_ok()
+            && std::fs::rename(&meta_backup, dir.join("meta.json")).is_ok()
+        {
+            report.restored.push(id);
+        } else if dir.join(INSTALL_MARKER).is_file() && std::fs::remove_dir_all(&dir).is_ok() {
+            report.removed.push(id);
+        }
+    }
+
+    report
+}
+
+/// 保存恢复结果，供前端下次读取（覆盖之前未读取的结果）
+pub fn save_report(plugins_dir: &Path, report: &RecoveryReport) -> Result<(), AppError> {
+    let json = serde_json::to_string_pretty(report)
+        .map_err(|e| AppError::internal(format!("序列化恢复结果失败: {e}")))?;
+    std::fs::write(plugins_dir.join(REPORT_FILE), json)
+        .map_err(|e| AppError::io(format!("写入恢复结果失败: {e}")))
+}
+
+/// 读取并删除保存的恢复结果
+pub async fn take_report(plugins_dir: &Path) -> Result<Option<RecoveryReport>, AppError> {
+    let path = plugins_dir.join(REPORT_FILE);
+    let content = match tokio::fs::read_to_string(&path).await {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(AppError::io(format!("读取恢复结果失败: {e}"))),
+    };
+    tokio::fs::remove_file(&path)
+        .await
+        .map_err(|e| AppError::io(format!("删除恢复结果失败: {e}")))?;
+    serde_json::from_str(&content)
+        .map(Some)
+        .map_err(|e| AppError::parse(format!("解析恢复结果失败: {e}")))
+}
+
+fn list_dirs(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| e.path().is_dir())
+        .map(|e| (e.file_name().to_string_lossy().to_string(), e.path()))
+        .collect()
+}
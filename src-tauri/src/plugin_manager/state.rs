@@ -0,0 +1,89 @@
+use super::PluginMeta;
+use crate::error::AppError;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+// ── 插件安装状态 ────────────────────────────────────────────
+//
+// 每个插件目录下的 state.json 记录安装与更新时间、被替换的上一个版本、来源 registry
+// 与 tarball 完整性。安装、更新、回滚时随新版本一起写入暂存目录，不依赖文件时间戳。
+
+/// 状态文件名
+pub const STATE_FILE: &str = "state.json";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginState {
+    /// 首次安装时间（毫秒时间戳）
+    pub installed_at: u64,
+    /// 最近一次安装、更新或回滚的时间
+    pub updated_at: u64,
+    /// 最近一次更新前的版本
+    #[serde(default)]
+    pub previous_version: Option<String>,
+    #[serde(default)]
+    pub registry: Option<String>,
+    /// tarball 的 SRI 哈希
+    #[serde(default)]
+    pub integrity: Option<String>,
+}
+
+impl PluginState {
+    /// 写入 `plugin` 后的状态
+    ///
+    /// `current` 为当前已安装的状态与版本：沿用其首次安装时间，并把其版本记为上一个版本。
+    pub fn replace(current: Option<(&PluginState, &str)>, plugin: &PluginMeta) -> Self {
+        let now = super::now_ms();
+        Self {
+            installed_at: current.map(|(state, _)| state.installed_at).unwrap_or(now),
+            updated_at: now,
+            previous_version: current.map(|(_, version)| version.to_string()),
+            registry: plugin.registry.clone(),
+            integrity: plugin.integrity.clone(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String, AppError> {
+        serde_json::to_string_pretty(self)
+            .map_err(|e| AppError::internal(format!("序列化插件状态失败: {e}")))
+    }
+}
+
+/// 读取插件状态
+///
+/// 旧版本安装的插件没有 state.json，此时以 meta.json 的修改时间为准补写一份，
+/// 之后不再依赖文件时间戳。
+pub async fn read(plugin_dir: &Path, meta: &PluginMeta) -> PluginState {
+    if let Ok(content) = tokio::fs::read_to_string(plugin_dir.join(STATE_FILE)).await {
+        if let Ok(state) = serde_json::from_str::<PluginState>(&content) {
+            return state;
+        }
+    }
+
+    let modified = tokio::fs::metadata(plugin_dir.join("meta.json"))
+        .await
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_else(super::now_ms);
+    let state = PluginState {
+        installed_at: modified,
+        updated_at: modified,
+        previous_version: None,
+        registry: meta.registry.clone(),
+        integrity: meta.integrity.clone(),
+    };
+    if let Ok(json) = state.to_json() {
+        let _ = tokio::fs::write(plugin_dir.join(STATE_FILE), json).await;
+    }
+    state
+}
+
+/// 写入状态文件（通常写入暂存目录）
+pub async fn write(dir: &Path, state: &PluginState) -> Result<(), AppError> {
+    tokio::fs::write(dir.join(STATE_FILE), state.to_json()?)
+        .await
+        .map_err(|e| AppError::io(format!("写入 {STATE_FILE} 失败: {e}")))
+}
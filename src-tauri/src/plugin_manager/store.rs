@@ -0,0 +1,107 @@
+use super::client::{HttpClient, HttpClients};
+use super::progress::{InstallProgress, InstallTasks};
+use crate::error::AppError;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+// ── 插件存储 ────────────────────────────────────────────────
+//
+// 插件管理操作需要的全部环境：plugins 目录、HTTP 客户端、正在进行的安装任务与安装进度
+// 的接收方。Tauri 命令从 AppHandle 构造后直接调用这里的方法；命令行工具与测试基于任意
+// 目录构造，不依赖 Tauri 运行时。
+
+/// 与 tauri.conf.json 中的 identifier 一致，决定应用数据目录的位置
+pub const APP_IDENTIFIER: &str = "com.cnccbz.usefultools";
+
+/// 应用数据目录下的插件目录名
+const PLUGINS_DIR: &str = "plugins";
+
+/// 安装进度的接收方
+pub type ProgressSink = Arc<dyn Fn(&InstallProgress) + Send + Sync>;
+
+#[derive(Clone)]
+pub struct PluginStore {
+    plugins_dir: PathBuf,
+    http: Arc<dyn HttpClient>,
+    tasks: InstallTasks,
+    progress: Option<ProgressSink>,
+}
+
+impl PluginStore {
+    /// 基于 plugins 目录创建，使用独立的 HTTP 客户端与任务表，不报告进度
+    pub fn new(plugins_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            plugins_dir: plugins_dir.into(),
+            http: Arc::new(HttpClients::default()),
+            tasks: InstallTasks::default(),
+            progress: None,
+        }
+    }
+
+    /// 使用应用默认的数据目录（与桌面应用相同）
+    pub fn open_default() -> Result<Self, AppError> {
+        Ok(Self::new(default_plugins_dir()?))
+    }
+
+    /// 由 Tauri 应用构造：共享托管状态，并把进度作为事件发送给前端
+    pub fn from_app(app: &tauri::AppHandle) -> Result<Self, AppError> {
+        use tauri::{Emitter, Manager};
+
+        let base = app
+            .path()
+            .app_data_dir()
+            .map_err(|e| AppError::io(format!("无法获取应用数据目录: {e}")))?;
+        let events = app.clone();
+        Ok(Self {
+            plugins_dir: base.join(PLUGINS_DIR),
+            http: Arc::new(app.state::<HttpClients>().inner().clone()),
+            tasks: app.state::<InstallTasks>().inner().clone(),
+            progress: Some(Arc::new(move |progress: &InstallProgress| {
+                let _ = events.emit(super::progress::INSTALL_PROGRESS_EVENT, progress);
+            })),
+        })
+    }
+
+    /// 替换 HTTP 客户端
+    pub fn with_http_client(mut self, http: impl HttpClient + 'static) -> Self {
+        self.http = Arc::new(http);
+        self
+    }
+
+    /// 设置安装进度的接收方
+    pub fn with_progress(
+        mut self,
+        sink: impl Fn(&InstallProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(Arc::new(sink));
+        self
+    }
+
+    pub fn plugins_dir(&self) -> &Path {
+        &self.plugins_dir
+    }
+
+    /// 单个插件的目录（会先校验 id）
+    pub fn plugin_dir(&self, plugin_id: &str) -> Result<PathBuf, AppError> {
+        super::validate_plugin_id(plugin_id)?;
+        Ok(self.plugins_dir.join(plugin_id))
+    }
+
+    pub fn http(&self) -> &dyn HttpClient {
+        self.http.as_ref()
+    }
+
+    pub fn tasks(&self) -> &InstallTasks {
+        &self.tasks
+    }
+
+    pub(super) fn progress(&self) -> Option<ProgressSink> {
+        self.progress.clone()
+    }
+}
+
+/// 桌面应用使用的 plugins 目录（与 Tauri 的 app_data_dir 规则相同）
+pub fn default_plugins_dir() -> Result<PathBuf, AppError> {
+    let data_dir = dirs::data_dir().ok_or_else(|| AppError::io("无法获取应用数据目录"))?;
+    Ok(data_dir.join(APP_IDENTIFIER).join(PLUGINS_DIR))
+}
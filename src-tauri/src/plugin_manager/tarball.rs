@@ -0,0 +1,203 @@
+use crate::error::{AppError, ErrorCode};
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Component;
+use std::rc::Rc;
+
+// ── 安全解压 .tgz ───────────────────────────────────────────
+
+/// 压缩后（下载或读取的 .tgz 文件）大小上限
+pub const MAX_TARBALL_SIZE: u64 = 32 * 1024 * 1024;
+/// 解压后总大小上限
+pub const MAX_UNPACKED_SIZE: u64 = 64 * 1024 * 1024;
+/// 单个文件大小上限
+pub const MAX_ENTRY_SIZE: u64 = 32 * 1024 * 1024;
+/// 条目数量上限
+pub const MAX_ENTRIES: usize = 5_000;
+
+/// tarball 不安全或无法解析
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TarballError {
+    /// gzip / tar 格式错误
+    Corrupt(String),
+    /// 压缩后的 tarball 超过上限
+    CompressedTooLarge { size: u64, limit: u64 },
+    /// 解压后总大小超过上限
+    TooLarge { limit: u64 },
+    /// 单个文件超过上限
+    EntryTooLarge { path: String, size: u64, limit: u64 },
+    /// 条目数量超过上限
+    TooManyEntries { limit: usize },
+    /// 包含符号链接或硬链接
+    Link { path: String },
+    /// 条目路径为绝对路径或包含 ..
+    UnsafePath { path: String },
+}
+
+impl fmt::Display for TarballError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TarballError::Corrupt(e) => write!(f, "tarball 格式错误: {e}"),
+            TarballError::CompressedTooLarge { size, limit } => write!(
+                f,
+                "tarball 大小 {size} 字节，超过 {} MB 上限",
+                limit / 1024 / 1024
+            ),
+            TarballError::TooLarge { limit } => {
+                write!(f, "tarball 解压后超过 {} MB 上限", limit / 1024 / 1024)
+            }
+            TarballError::EntryTooLarge { path, size, limit } => write!(
+                f,
+                "tarball 中的文件 {path} 大小 {size} 字节，超过 {limit} 字节上限"
+            ),
+            TarballError::TooManyEntries { limit } => {
+                write!(f, "tarball 条目数量超过 {limit} 个上限")
+            }
+            TarballError::Link { path } => write!(f, "tarball 中包含不允许的链接: {path}"),
+            TarballError::UnsafePath { path } => write!(f, "tarball 中包含不安全的路径: {path}"),
+        }
+    }
+}
+
+impl std::error::Error for TarballError {}
+
+impl From<TarballError> for AppError {
+    fn from(e: TarballError) -> Self {
+        AppError::new(ErrorCode::InvalidPackage, e.to_string())
+    }
+}
+
+/// 检查压缩后的大小（Content-Length、已下载的字节数或 .tgz 文件大小）
+pub fn check_compressed_size(size: u64) -> Result<(), TarballError> {
+    if size > MAX_TARBALL_SIZE {
+        return Err(TarballError::CompressedTooLarge {
+            size,
+            limit: MAX_TARBALL_SIZE,
+        });
+    }
+    Ok(())
+}
+
+/// 统计已解压字节数的 reader，超过上限时报错
+struct LimitedReader<R> {
+    inner: R,
+    read: Rc<Cell<u64>>,
+    limit: u64,
+}
+
+impl<R: Read> Read for LimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        let total = self.read.get() + n as u64;
+        self.read.set(total);
+        if total > self.limit {
+            return Err(io::Error::other("unpacked size limit exceeded"));
+        }
+        Ok(n)
+    }
+}
+
+/// 相对路径且不含 `..`
+pub fn is_safe_relative_path(path: &str) -> bool {
+    !path.is_empty()
+        && std::path::Path::new(path)
+            .components()
+            .all(|c| matches!(c, Component::Normal(_) | Component::CurDir))
+}
+
+/// 扫描整个 tarball 并取出指定路径的文件内容
+///
+/// 任何链接条目、不安全路径或超出大小 / 数量上限都会使整个 tarball 被拒绝；
+/// 返回的 map 只包含实际存在的目标文件。
+pub fn extract_files(
+    tgz_bytes: &[u8],
+    targets: &[&str],
+) -> Result<HashMap<String, Vec<u8>>, TarballError> {
+    let mut found = HashMap::new();
+    scan(tgz_bytes, |path, size, entry| {
+        if targets.contains(&path) {
+            let mut buf = Vec::with_capacity(size as usize);
+            entry.read_to_end(&mut buf)?;
+            found.insert(path.to_string(), buf);
+        }
+        Ok(())
+    })?;
+    Ok(found)
+}
+
+/// 列出 tarball 中的所有条目：(路径, 大小)，检查规则与 extract_files 相同
+pub fn list_entries(tgz_bytes: &[u8]) -> Result<Vec<(String, u64)>, TarballError> {
+    let mut entries = Vec::new();
+    scan(tgz_bytes, |path, size, _| {
+        entries.push((path.to_string(), size));
+        Ok(())
+    })?;
+    Ok(entries)
+}
+
+/// 逐个检查条目，通过检查的条目交给 `visit`
+fn scan(
+    tgz_bytes: &[u8],
+    mut visit: impl FnMut(&str, u64, &mut dyn Read) -> io::Result<()>,
+) -> Result<(), TarballError> {
+    check_compressed_size(tgz_bytes.len() as u64)?;
+    let unpacked = Rc::new(Cell::new(0u64));
+    let reader = LimitedReader {
+        inner: flate2::read::GzDecoder::new(tgz_bytes),
+        read: unpacked.clone(),
+        limit: MAX_UNPACKED_SIZE,
+    };
+    let mut archive = tar::Archive::new(reader);
+
+    // 超出总大小上限导致的读取错误统一报告为 TooLarge
+    let corrupt = |e: io::Error| {
+        if unpacked.get() > MAX_UNPACKED_SIZE {
+            TarballError::TooLarge {
+                limit: MAX_UNPACKED_SIZE,
+            }
+        } else {
+            TarballError::Corrupt(e.to_string())
+        }
+    };
+
+    let mut count = 0usize;
+
+    for entry in archive.entries().map_err(corrupt)? {
+        let mut entry = entry.map_err(corrupt)?;
+
+        count += 1;
+        if count > MAX_ENTRIES {
+            return Err(TarballError::TooManyEntries { limit: MAX_ENTRIES });
+        }
+
+        let path = String::from_utf8_lossy(&entry.path_bytes()).to_string();
+        let entry_type = entry.header().entry_type();
+
+        if entry_type.is_symlink() || entry_type.is_hard_link() {
+            return Err(TarballError::Link { path });
+        }
+        if !is_safe_relative_path(&path) {
+            return Err(TarballError::UnsafePath { path });
+        }
+
+        let size = entry.header().size().map_err(corrupt)?;
+        if size > MAX_ENTRY_SIZE {
+            return Err(TarballError::EntryTooLarge {
+                path,
+                size,
+                limit: MAX_ENTRY_SIZE,
+            });
+        }
+
+        visit(&path, size, &mut entry).map_err(corrupt)?;
+    }
+
+    Ok(())
+}
+
+/// 取出单个文件，不存在时返回 None
+pub fn extract_file(tgz_bytes: &[u8], target: &str) -> Result<Option<Vec<u8>>, TarballError> {
+    Ok(extract_files(tgz_bytes, &[target])?.remove(target))
+}
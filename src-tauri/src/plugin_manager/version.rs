@@ -0,0 +1,46 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+// ── 版本比较 ────────────────────────────────────────────────
+
+/// 远端版本相对本地版本的变化类型
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum UpdateKind {
+    Major,
+    Minor,
+    Patch,
+    /// 仅预发布标识不同（如 1.0.0-beta.1 → 1.0.0-beta.2 / 1.0.0）
+    Prerelease,
+    /// 远端版本低于本地版本
+    Downgrade,
+}
+
+/// 解析 semver 版本号，允许带前缀 v
+pub fn parse_version(version: &str) -> Option<Version> {
+    let version = version.trim();
+    Version::parse(version.strip_prefix('v').unwrap_or(version)).ok()
+}
+
+/// 判断从 `current` 到 `remote` 属于哪种更新
+///
+/// 版本相同或任一版本不是合法 semver 时返回 None。
+pub fn classify_update(current: &str, remote: &str) -> Option<UpdateKind> {
+    let current = parse_version(current)?;
+    let remote = parse_version(remote)?;
+
+    match remote.cmp_precedence(&current) {
+        Ordering::Equal => None,
+        Ordering::Less => Some(UpdateKind::Downgrade),
+        Ordering::Greater => Some(if remote.major != current.major {
+            UpdateKind::Major
+        } else if remote.minor != current.minor {
+            UpdateKind::Minor
+        } else if remote.patch != current.patch {
+            UpdateKind::Patch
+        } else {
+            UpdateKind::Prerelease
+        }),
+    }
+}
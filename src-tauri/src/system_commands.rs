@@ -1,3 +1,4 @@
+use crate::error::{AppError, ErrorCode};
 use serde::{Deserialize, Serialize};
 use sysinfo::{Disks, Networks, System};
 
@@ -150,7 +151,7 @@ pub fn get_processes() -> Vec<ProcessInfo> {
         .collect();
 
     // 按内存占用降序排列，取前 50 个
-    processes.sort_by(|a, b| b.memory.cmp(&a.memory));
+    processes.sort_by_key(|p| std::cmp::Reverse(p.memory));
     processes.truncate(50);
     processes
 }
@@ -172,17 +173,20 @@ pub fn get_network_info() -> Vec<NetworkInterfaceInfo> {
 /// 白名单限制的 Shell 命令执行
 /// 仅允许执行: ping, ipconfig, systeminfo, whoami, hostname
 #[tauri::command]
-pub async fn execute_shell(command: String, args: Vec<String>) -> Result<ShellOutput, String> {
+pub async fn execute_shell(command: String, args: Vec<String>) -> Result<ShellOutput, AppError> {
     const WHITELIST: &[&str] = &["ping", "ipconfig", "systeminfo", "whoami", "hostname"];
 
     if !WHITELIST.contains(&command.to_lowercase().as_str()) {
-        return Err(format!("命令不在白名单中: {command}"));
+        return Err(AppError::new(
+            ErrorCode::Forbidden,
+            format!("命令不在白名单中: {command}"),
+        ));
     }
 
     let output = std::process::Command::new(&command)
         .args(&args)
         .output()
-        .map_err(|e| format!("执行命令失败: {e}"))?;
+        .map_err(|e| AppError::io(format!("执行命令失败: {e}")))?;
 
     Ok(ShellOutput {
         stdout: String::from_utf8_lossy(&output.stdout).to_string(),
@@ -0,0 +1,241 @@
+//! 集成测试共用的辅助
+//!
+//! 每个测试文件通过 `mod common;` 引入，未用到的辅助不报警告。
+
+#![allow(dead_code)]
+
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+// ── 临时目录 ────────────────────────────────────────────────
+
+/// 独立的临时目录，离开作用域时删除
+pub struct TempDir(pub PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "usefultools-test-{name}-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+// ── 模拟 HTTP 服务 ──────────────────────────────────────────
+
+/// 模拟服务收到的请求
+#[derive(Clone, Debug)]
+pub struct Request {
+    pub method: String,
+    /// 路径与查询参数
+    pub target: String,
+    /// 请求头（名称为小写）
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// 不含查询参数的路径
+    pub fn path(&self) -> &str {
+        self.target.split('?').next().unwrap_or("/")
+    }
+
+    /// 查询参数
+    pub fn query(&self, key: &str) -> Option<&str> {
+        self.target
+            .split_once('?')?
+            .1
+            .split('&')
+            .find_map(|pair| pair.strip_prefix(key)?.strip_prefix('='))
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    pub fn new(status: u16, content_type: &'static str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into(),
+        }
+    }
+
+    pub fn json(status: u16, body: impl Into<Vec<u8>>) -> Self {
+        Self::new(status, "application/json", body)
+    }
+}
+
+type Handler = Arc<dyn Fn(&Request) -> Option<Response> + Send + Sync>;
+
+/// 只在本机监听的 HTTP 服务，用来模拟 registry
+///
+/// 先交给自定义的处理函数，未处理时按路径（不含查询参数）返回预设响应，
+/// 都没有时返回 404。
+#[derive(Clone)]
+pub struct MockServer {
+    pub url: String,
+    routes: Arc<Mutex<HashMap<String, Response>>>,
+    requests: Arc<Mutex<Vec<Request>>>,
+    handler: Option<Handler>,
+}
+
+impl MockServer {
+    pub fn start() -> Self {
+        Self::spawn(None)
+    }
+
+    /// 由处理函数决定响应，返回 None 时按预设路由处理
+    pub fn with_handler(
+        handler: impl Fn(&Request) -> Option<Response> + Send + Sync + 'static,
+    ) -> Self {
+        Self::spawn(Some(Arc::new(handler)))
+    }
+
+    fn spawn(handler: Option<Handler>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let server = MockServer {
+            url: format!("http://{}", listener.local_addr().unwrap()),
+            routes: Default::default(),
+            requests: Default::default(),
+            handler,
+        };
+        let handle = server.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                handle.handle(stream);
+            }
+        });
+        server
+    }
+
+    fn handle(&self, mut stream: TcpStream) {
+        let Some(request) = read_request(&mut stream) else {
+            return;
+        };
+        self.requests.lock().unwrap().push(request.clone());
+
+        let response = self
+            .handler
+            .as_ref()
+            .and_then(|handler| handler(&request))
+            .or_else(|| self.routes.lock().unwrap().get(request.path()).cloned())
+            .unwrap_or_else(|| Response::json(404, r#"{"error":"not found"}"#));
+        let header = format!(
+            "HTTP/1.1 {} MOCK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            response.status,
+            response.content_type,
+            response.body.len()
+        );
+        let _ = stream.write_all(header.as_bytes());
+        let _ = stream.write_all(&response.body);
+    }
+
+    pub fn route(&self, path: &str, response: Response) {
+        self.routes
+            .lock()
+            .unwrap()
+            .insert(path.to_string(), response);
+    }
+
+    pub fn json(&self, path: &str, body: serde_json::Value) {
+        self.route(path, Response::json(200, body.to_string()));
+    }
+
+    /// 收到的全部请求
+    pub fn requests(&self) -> Vec<Request> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    /// 收到的、路径与查询参数以 prefix 开头的请求数
+    pub fn count(&self, prefix: &str) -> usize {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|r| r.target.starts_with(prefix))
+            .count()
+    }
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut buf = [0u8; 4096];
+    let head_end = loop {
+        if let Some(pos) = data.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => return None,
+            Ok(n) => data.extend_from_slice(&buf[..n]),
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let target = request_line.next().unwrap_or("/").to_string();
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0);
+    let mut body = data[head_end..].to_vec();
+    while body.len() < length {
+        match stream.read(&mut buf) {
+            Ok(0) | Err(_) => break,
+            Ok(n) => body.extend_from_slice(&buf[..n]),
+        }
+    }
+
+    Some(Request {
+        method,
+        target,
+        headers,
+        body,
+    })
+}
+
+// ── tarball ─────────────────────────────────────────────────
+
+/// 逐条构造 .tgz（用于构造正常打包不会生成的异常包）
+pub fn build_tgz(build: impl FnOnce(&mut tar::Builder<GzEncoder<Vec<u8>>>)) -> Vec<u8> {
+    let mut builder = tar::Builder::new(GzEncoder::new(Vec::new(), Compression::fast()));
+    build(&mut builder);
+    builder.into_inner().unwrap().finish().unwrap()
+}
+
+pub fn append_file(builder: &mut tar::Builder<impl Write>, path: &str, data: &[u8]) {
+    let mut header = tar::Header::new_gnu();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_entry_type(tar::EntryType::Regular);
+    builder.append_data(&mut header, path, data).unwrap();
+}
@@ -0,0 +1,168 @@
+//! 按网络设置创建的 HTTP 客户端与 registry 请求的重试
+
+mod common;
+
+use common::{MockServer, Response};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::auth::Credentials;
+use usefultools_lib::plugin_manager::client::{NetworkConfig, RegistryClient};
+
+/// 直连本机、不等待退避的设置
+fn local(retries: u32) -> NetworkConfig {
+    NetworkConfig {
+        no_proxy: vec!["127.0.0.1".to_string()],
+        retries,
+        retry_backoff_ms: 1,
+        ..Default::default()
+    }
+}
+
+/// 前 failures 次请求返回 status，之后返回 200
+fn flaky_server(failures: usize, status: u16) -> (MockServer, Arc<AtomicUsize>) {
+    let count = Arc::new(AtomicUsize::new(0));
+    let seen = count.clone();
+    let server = MockServer::with_handler(move |_| {
+        let n = seen.fetch_add(1, Ordering::SeqCst);
+        Some(if n < failures {
+            Response::json(status, "{}")
+        } else {
+            Response::json(200, r#"{"ok":true}"#)
+        })
+    });
+    (server, count)
+}
+
+fn registry_client(config: &NetworkConfig) -> RegistryClient {
+    RegistryClient::new(config.build_client().unwrap(), Credentials::default()).with_retry(config)
+}
+
+// ── 重试 ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn retries_server_errors_until_success() {
+    let (server, count) = flaky_server(2, 503);
+    let client = registry_client(&local(2));
+
+    let response = client.send(client.get(&server.url)).await.unwrap();
+
+    assert_eq!(response.status(), 200);
+    assert_eq!(count.load(Ordering::SeqCst), 3);
+}
+
+#[tokio::test]
+async fn gives_up_after_the_configured_retries() {
+    let (server, count) = flaky_server(10, 429);
+    let client = registry_client(&local(1));
+
+    let response = client.send(client.get(&server.url)).await.unwrap();
+
+    assert_eq!(response.status(), 429);
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test]
+async fn client_errors_are_not_retried() {
+    let (server, count) = flaky_server(10, 404);
+    let client = registry_client(&local(3));
+
+    let response = client.send(client.get(&server.url)).await.unwrap();
+
+    assert_eq!(response.status(), 404);
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+// ── 客户端设置 ──────────────────────────────────────────────
+
+#[tokio::test]
+async fn user_agent_defaults_to_the_app_version() {
+    let server = MockServer::start();
+    let mut config = local(0);
+
+    let client = config.build_client().unwrap();
+    client.get(&server.url).send().await.unwrap();
+    config.user_agent = Some("custom-agent/1.0".to_string());
+    let client = config.build_client().unwrap();
+    client.get(&server.url).send().await.unwrap();
+
+    let agents: Vec<String> = server
+        .requests()
+        .iter()
+        .map(|r| r.headers["user-agent"].clone())
+        .collect();
+    assert_eq!(
+        agents,
+        [
+            format!("usefultools/{}", env!("CARGO_PKG_VERSION")),
+            "custom-agent/1.0".to_string(),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn read_timeout_aborts_a_stalled_response() {
+    let server = MockServer::with_handler(|_| {
+        std::thread::sleep(Duration::from_secs(3));
+        Some(Response::json(200, "{}"))
+    });
+    let config = NetworkConfig {
+        read_timeout_secs: 1,
+        ..local(0)
+    };
+
+    let started = Instant::now();
+    let error = config
+        .build_client()
+        .unwrap()
+        .get(&server.url)
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(error.is_timeout(), "{error}");
+    assert!(started.elapsed() < Duration::from_secs(3));
+}
+
+#[tokio::test]
+async fn request_timeout_limits_the_whole_request() {
+    let server = MockServer::with_handler(|_| {
+        std::thread::sleep(Duration::from_secs(3));
+        Some(Response::json(200, "{}"))
+    });
+    let config = NetworkConfig {
+        read_timeout_secs: 30,
+        request_timeout_secs: 1,
+        ..local(0)
+    };
+
+    let started = Instant::now();
+    let error = config
+        .build_client()
+        .unwrap()
+        .get(&server.url)
+        .send()
+        .await
+        .unwrap_err();
+
+    assert!(error.is_timeout(), "{error}");
+    assert!(started.elapsed() < Duration::from_secs(3));
+}
+
+#[test]
+fn invalid_proxy_and_certificates_are_rejected() {
+    let proxy = NetworkConfig {
+        proxy: Some("http://[::1".to_string()),
+        ..Default::default()
+    };
+    assert!(proxy.build_client().is_err());
+
+    let ca = NetworkConfig {
+        ca_certificates: Some("not a certificate".to_string()),
+        ..Default::default()
+    };
+    let error = ca.build_client().unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidInput);
+    assert!(error.message.contains("根证书无效"), "{error}");
+}
@@ -0,0 +1,168 @@
+//! 暂存目录换入，以及启动时对中断安装的恢复：只处理应用创建的插件目录，结果保存供前端读取
+
+mod common;
+
+use common::TempDir;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use usefultools_lib::error::{AppError, ErrorCode};
+use usefultools_lib::plugin_manager::staging::{self, INSTALL_MARKER, OLD_DIR, STAGING_DIR};
+use usefultools_lib::plugin_manager::PluginStore;
+
+/// 写入一个完整的插件目录
+fn write_plugin(dir: &Path, bundle: &str) {
+    fs::create_dir_all(dir).unwrap();
+    fs::write(dir.join("bundle.mjs"), bundle).unwrap();
+    fs::write(dir.join("meta.json"), "{}").unwrap();
+}
+
+fn bundle(dir: &Path) -> String {
+    fs::read_to_string(dir.join("bundle.mjs")).unwrap()
+}
+
+// ── 换入 ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn commit_swaps_in_the_new_version_and_drops_the_old_one() {
+    let dir = TempDir::new("staging-commit");
+    write_plugin(&dir.0.join("demo"), "v1");
+
+    let staging = staging::create(&dir.0, "demo").await.unwrap();
+    write_plugin(&staging, "v2");
+    staging::finish(&dir.0, "demo", &staging, Ok(()))
+        .await
+        .unwrap();
+
+    assert_eq!(bundle(&dir.0.join("demo")), "v2");
+    assert!(!staging.exists());
+    assert!(!dir.0.join(OLD_DIR).join("demo").exists());
+}
+
+#[tokio::test]
+async fn failed_preparation_keeps_the_installed_version() {
+    let dir = TempDir::new("staging-failed");
+    write_plugin(&dir.0.join("demo"), "v1");
+
+    let staging = staging::create(&dir.0, "demo").await.unwrap();
+    fs::write(staging.join("bundle.mjs"), "half").unwrap();
+    let result =
+        staging::finish(&dir.0, "demo", &staging, Err(AppError::network("下载中断"))).await;
+
+    assert_eq!(result.unwrap_err().code, ErrorCode::Network);
+    assert_eq!(bundle(&dir.0.join("demo")), "v1");
+    assert!(!staging.exists());
+}
+
+// ── 启动时恢复 ──────────────────────────────────────────────
+
+#[test]
+fn interrupted_swap_restores_the_previous_version() {
+    let dir = TempDir::new("recover-swap");
+    // 旧版本已移入 .old，新版本还没换入
+    write_plugin(&dir.0.join(OLD_DIR).join("demo"), "v1");
+    fs::create_dir_all(dir.0.join(STAGING_DIR).join("demo-1")).unwrap();
+
+    let report = staging::recover(&dir.0);
+
+    assert_eq!(report.restored, ["demo"]);
+    assert_eq!(report.discarded_staging, 1);
+    assert_eq!(bundle(&dir.0.join("demo")), "v1");
+    assert!(!dir.0.join(OLD_DIR).exists());
+    assert!(!dir.0.join(STAGING_DIR).exists());
+}
+
+#[test]
+fn completed_swap_only_drops_the_old_version() {
+    let dir = TempDir::new("recover-done");
+    write_plugin(&dir.0.join(OLD_DIR).join("demo"), "v1");
+    write_plugin(&dir.0.join("demo"), "v2");
+
+    let report = staging::recover(&dir.0);
+
+    assert!(report.restored.is_empty());
+    assert_eq!(bundle(&dir.0.join("demo")), "v2");
+    assert!(!dir.0.join(OLD_DIR).exists());
+}
+
+#[test]
+fn half_written_dirs_fall_back_to_backups_or_are_removed() {
+    let dir = TempDir::new("recover-half");
+    // 旧版本直接覆盖写入时中断，留下了备份
+    let rolled = dir.0.join("rolled");
+    fs::create_dir_all(&rolled).unwrap();
+    fs::write(rolled.join("bundle.mjs.bak"), "v1").unwrap();
+    fs::write(rolled.join("meta.json.bak"), "{}").unwrap();
+    // 应用创建、没有备份的不完整目录
+    fs::create_dir_all(dir.0.join("broken")).unwrap();
+    fs::write(dir.0.join("broken/meta.json"), "{}").unwrap();
+    fs::write(dir.0.join("broken").join(INSTALL_MARKER), "").unwrap();
+    // 不是应用创建的目录
+    fs::create_dir_all(dir.0.join("notes")).unwrap();
+    fs::write(dir.0.join("notes/readme.txt"), "keep me").unwrap();
+    write_plugin(&dir.0.join("intact"), "v1");
+
+    let report = staging::recover(&dir.0);
+
+    assert_eq!(report.restored, ["rolled"]);
+    assert_eq!(report.removed, ["broken"]);
+    assert_eq!(bundle(&rolled), "v1");
+    assert!(!dir.0.join("broken").exists());
+    assert!(dir.0.join("notes/readme.txt").is_file());
+    assert_eq!(bundle(&dir.0.join("intact")), "v1");
+}
+
+// ── 恢复结果 ────────────────────────────────────────────────
+
+/// 按插件作者的目录布局写入只含一个工具的插件
+fn write_source(dir: &Path, id: &str) {
+    fs::create_dir_all(dir.join("dist")).unwrap();
+    let package_json = json!({ "name": format!("usefultools-plugin-{id}"), "version": "1.0.0" });
+    fs::write(dir.join("package.json"), package_json.to_string()).unwrap();
+    let plugin_json = json!({
+        "id": id,
+        "version": "1.0.0",
+        "author": "tester",
+        "icon": "build",
+        "title": format!("Tool {id}"),
+        "subtitle": "test",
+        "description": "recovery test plugin",
+        "bgColor": "bg-blue-500",
+        "categories": ["其他"],
+        "bundle": format!("dist/{id}.mjs"),
+    });
+    fs::write(dir.join("plugin.json"), plugin_json.to_string()).unwrap();
+    fs::write(dir.join(format!("dist/{id}.mjs")), "export default 1").unwrap();
+}
+
+#[tokio::test]
+async fn installed_plugin_is_recovered_and_the_report_read_once() {
+    let dir = TempDir::new("recover-store");
+    let store = PluginStore::new(&dir.0);
+
+    // 应用安装的插件，之后 bundle 丢失
+    let source = TempDir::new("recover-source");
+    write_source(&source.0, "demo");
+    store
+        .install_plugin_from_file(source.0.to_string_lossy().to_string(), None)
+        .await
+        .unwrap();
+    fs::remove_file(dir.0.join("demo/bundle.mjs")).unwrap();
+
+    let report = store.recover_interrupted_installs();
+    assert_eq!(report.removed, ["demo"]);
+    assert!(!dir.0.join("demo").exists());
+
+    let saved = store.take_recovery_report().await.unwrap().unwrap();
+    assert_eq!(saved.removed, ["demo"]);
+    assert!(store.take_recovery_report().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn nothing_to_recover_saves_no_report() {
+    let dir = TempDir::new("recover-clean");
+    let store = PluginStore::new(&dir.0);
+
+    assert!(store.recover_interrupted_installs().is_empty());
+    assert!(store.take_recovery_report().await.unwrap().is_none());
+}
@@ -0,0 +1,159 @@
+//! npm search 分页：按 total 翻页、去重、安全上限与出错时的处理
+
+mod common;
+
+use common::{MockServer, Response};
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::client::RegistryClient;
+use usefultools_lib::plugin_manager::search::{search_all, MAX_RESULTS, PAGE_SIZE};
+
+/// 启动模拟 registry，搜索请求交给 page_at 处理
+///
+/// page_at 接收 `from` 参数，返回 (状态码, 响应体)。
+fn mock_search(page_at: impl Fn(usize) -> (u16, String) + Send + Sync + 'static) -> MockServer {
+    MockServer::with_handler(move |request| {
+        let from = request.query("from").and_then(|v| v.parse().ok());
+        let (status, body) = page_at(from.unwrap_or(0));
+        Some(Response::json(status, body))
+    })
+}
+
+/// 构造一页搜索结果
+fn page(names: impl IntoIterator<Item = String>, total: usize) -> (u16, String) {
+    let objects: Vec<String> = names
+        .into_iter()
+        .map(|name| format!(r#"{{"package":{{"name":"{name}","version":"1.0.0"}}}}"#))
+        .collect();
+    (
+        200,
+        format!(r#"{{"objects":[{}],"total":{total}}}"#, objects.join(",")),
+    )
+}
+
+fn names(range: std::ops::Range<usize>) -> impl Iterator<Item = String> {
+    range.map(|i| format!("usefultools-plugin-{i}"))
+}
+
+// ── 分页 ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn follows_total_across_pages() {
+    let total = PAGE_SIZE * 2 + 17;
+    let registry = mock_search(move |from| page(names(from..(from + PAGE_SIZE).min(total)), total));
+
+    let result = search_all(
+        &RegistryClient::default(),
+        &registry.url,
+        "usefultools-plugin",
+    )
+    .await
+    .unwrap()
+    .names;
+
+    assert_eq!(result, names(0..total).collect::<Vec<_>>());
+    let requests = registry.requests();
+    assert_eq!(requests.len(), 3);
+    assert!(requests[0]
+        .target
+        .starts_with("/-/v1/search?text=usefultools-plugin&size=250&from=0"));
+    assert_eq!(requests[2].query("from"), Some("500"));
+}
+
+#[tokio::test]
+async fn deduplicates_results_shifted_between_pages() {
+    // 第二页与第一页重叠 10 条（翻页期间有新包发布导致结果偏移）
+    let total = PAGE_SIZE + 40;
+    let registry = mock_search(move |from| {
+        let start = from.saturating_sub(10);
+        page(names(start..(start + PAGE_SIZE).min(total)), total)
+    });
+
+    let result = search_all(&RegistryClient::default(), &registry.url, "q")
+        .await
+        .unwrap()
+        .names;
+
+    assert_eq!(result, names(0..total).collect::<Vec<_>>());
+}
+
+#[tokio::test]
+async fn stops_at_safety_cap() {
+    let registry = mock_search(|from| page(names(from..from + PAGE_SIZE), 10_000_000));
+
+    let result = search_all(&RegistryClient::default(), &registry.url, "q")
+        .await
+        .unwrap()
+        .names;
+
+    assert_eq!(result.len(), MAX_RESULTS);
+    assert_eq!(registry.requests().len(), MAX_RESULTS.div_ceil(PAGE_SIZE));
+}
+
+#[tokio::test]
+async fn stops_on_empty_page() {
+    let registry = mock_search(|from| {
+        if from == 0 {
+            page(names(0..PAGE_SIZE), 1_000)
+        } else {
+            page(names(0..0), 1_000)
+        }
+    });
+
+    let result = search_all(&RegistryClient::default(), &registry.url, "q")
+        .await
+        .unwrap()
+        .names;
+
+    assert_eq!(result.len(), PAGE_SIZE);
+    assert_eq!(registry.requests().len(), 2);
+}
+
+#[tokio::test]
+async fn single_page_without_total() {
+    let registry = mock_search(|_| {
+        let (status, body) = page(names(0..3), 0);
+        (status, body.replace(r#","total":0"#, ""))
+    });
+
+    let result = search_all(&RegistryClient::default(), &registry.url, "q")
+        .await
+        .unwrap()
+        .names;
+
+    assert_eq!(result.len(), 3);
+    assert_eq!(registry.requests().len(), 1);
+}
+
+// ── 错误处理 ────────────────────────────────────────────────
+
+#[tokio::test]
+async fn first_page_failure_is_an_error() {
+    let registry = mock_search(|_| (500, "{}".to_string()));
+
+    let err = search_all(&RegistryClient::default(), &registry.url, "q")
+        .await
+        .unwrap_err();
+    assert_eq!(err.code, ErrorCode::Http, "{err}");
+    assert_eq!(err.context.status, Some(500));
+}
+
+#[tokio::test]
+async fn later_page_failure_keeps_partial_results() {
+    let registry = mock_search(|from| {
+        if from == 0 {
+            page(names(0..PAGE_SIZE), PAGE_SIZE * 3)
+        } else {
+            (503, String::new())
+        }
+    });
+
+    let result = search_all(&RegistryClient::default(), &registry.url, "q")
+        .await
+        .unwrap();
+
+    assert_eq!(result.names, names(0..PAGE_SIZE).collect::<Vec<_>>());
+    let warning = result.incomplete.expect("部分结果应带上警告");
+    assert_eq!(warning.code, ErrorCode::Http, "{warning}");
+    assert_eq!(warning.context.status, Some(503));
+    assert!(warning.message.contains("第 250 条之后"), "{warning}");
+}
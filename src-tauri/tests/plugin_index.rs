@@ -0,0 +1,294 @@
+//! 已安装插件索引：文件读写与跨命令的锁，各操作后保持同步、损坏时重建、修复时报告异常目录
+
+mod common;
+
+use common::TempDir;
+use serde_json::json;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::index::{
+    self, InstalledIndex, CORRUPT_INDEX_FILE, INDEX_FILE,
+};
+use usefultools_lib::plugin_manager::PluginStore;
+
+/// 含一个插件的索引
+fn sample_index() -> InstalledIndex {
+    serde_json::from_value(json!({
+        "version": 1,
+        "plugins": {
+            "demo": {
+                "meta": {
+                    "id": "demo",
+                    "version": "1.2.0",
+                    "author": "tester",
+                    "icon": "build",
+                    "title": "Demo",
+                    "subtitle": "",
+                    "description": "",
+                    "bgColor": "bg-blue-500",
+                    "categories": ["其他"],
+                    "requires": [],
+                    "packageName": "usefultools-plugin-demo",
+                    "bundleFile": "demo.mjs",
+                },
+                "installedAt": 1,
+                "updatedAt": 2,
+                "localBundlePath": "/plugins/demo/bundle.mjs",
+                "enabled": true,
+                "pinnedVersion": "1.2.0",
+            }
+        }
+    }))
+    .unwrap()
+}
+
+#[tokio::test]
+async fn written_index_is_read_back() {
+    let dir = TempDir::new("index-write");
+    let _lock = index::lock(&dir.0).await.unwrap();
+
+    index::write(&dir.0, &sample_index()).await.unwrap();
+    let read = index::read(&dir.0)
+        .await
+        .unwrap()
+        .expect("刚写入的索引应可读取");
+
+    let demo = &read.plugins["demo"];
+    assert_eq!(demo.meta.version, "1.2.0");
+    assert_eq!(demo.pinned_version.as_deref(), Some("1.2.0"));
+    // 临时文件已换入，不留残留
+    let mut files: Vec<String> = fs::read_dir(&dir.0)
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    files.sort();
+    assert_eq!(files, [INDEX_FILE, "installed-plugins.lock"]);
+}
+
+#[tokio::test]
+async fn missing_or_unknown_version_index_needs_a_rebuild_and_corrupt_is_an_error() {
+    let dir = TempDir::new("index-corrupt");
+    assert!(index::read(&dir.0).await.unwrap().is_none());
+
+    fs::write(dir.0.join(INDEX_FILE), r#"{"version": 99, "plugins": {}}"#).unwrap();
+    assert!(index::read(&dir.0).await.unwrap().is_none());
+
+    fs::write(dir.0.join(INDEX_FILE), "{\"version\": 1, \"plugins\": {").unwrap();
+    let error = index::read(&dir.0).await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::Parse, "{error}");
+
+    fs::write(dir.0.join(INDEX_FILE), r#"{"version": 1, "plugins": {}}"#).unwrap();
+    assert!(index::read(&dir.0).await.unwrap().is_some());
+    index::invalidate(&dir.0);
+    assert!(!dir.0.join(INDEX_FILE).exists());
+}
+
+#[tokio::test]
+async fn lock_is_held_until_dropped() {
+    let dir = TempDir::new("index-lock");
+    let first = index::lock(&dir.0).await.unwrap();
+    let lock_file = dir.0.join("installed-plugins.lock");
+    assert!(lock_file.is_file());
+
+    let plugins_dir = dir.0.clone();
+    let second = tokio::spawn(async move { index::lock(&plugins_dir).await.map(|_| ()) });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!second.is_finished());
+
+    drop(first);
+    second.await.unwrap().unwrap();
+}
+
+#[tokio::test]
+async fn lock_waits_for_other_processes_but_not_for_a_leftover_lock_file() {
+    let dir = TempDir::new("index-lock-os");
+    let lock_file = dir.0.join("installed-plugins.lock");
+
+    // 崩溃的进程留下的锁文件没有持有者，不会阻塞
+    fs::create_dir_all(&dir.0).unwrap();
+    fs::write(&lock_file, "").unwrap();
+    drop(index::lock(&dir.0).await.unwrap());
+
+    // 另一个进程持有文件锁时等待其释放
+    let other = fs::File::open(&lock_file).unwrap();
+    other.lock().unwrap();
+    let plugins_dir = dir.0.clone();
+    let waiting = tokio::spawn(async move { index::lock(&plugins_dir).await.map(|_| ()) });
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    assert!(!waiting.is_finished());
+
+    drop(other);
+    waiting.await.unwrap().unwrap();
+}
+
+// ── PluginStore ─────────────────────────────────────────────
+
+/// 直接读取磁盘上的索引文件（不经过 PluginStore，避免触发重建）
+fn index_on_disk(plugins_dir: &Path) -> serde_json::Value {
+    let content = fs::read_to_string(plugins_dir.join(INDEX_FILE)).unwrap();
+    serde_json::from_str(&content).unwrap()
+}
+
+fn indexed_ids(plugins_dir: &Path) -> Vec<String> {
+    index_on_disk(plugins_dir)["plugins"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .cloned()
+        .collect()
+}
+
+/// 写入含 ids 各工具的插件目录并从该目录安装
+async fn install(store: &PluginStore, dir: &TempDir, name: &str, ids: &[&str]) {
+    let source = dir.0.join(format!("source-{name}"));
+    fs::create_dir_all(source.join("dist")).unwrap();
+    let package_json = json!({ "name": name, "version": "1.0.0" });
+    fs::write(source.join("package.json"), package_json.to_string()).unwrap();
+    let tools: Vec<_> = ids
+        .iter()
+        .map(|id| {
+            fs::write(source.join(format!("dist/{id}.mjs")), "export default 1").unwrap();
+            json!({
+                "id": id,
+                "version": "1.0.0",
+                "author": "tester",
+                "icon": "build",
+                "title": format!("Tool {id}"),
+                "subtitle": "test",
+                "description": "index test plugin",
+                "bgColor": "bg-blue-500",
+                "categories": ["其他"],
+                "bundle": format!("dist/{id}.mjs"),
+            })
+        })
+        .collect();
+    let plugin_json = json!({ "plugins": tools });
+    fs::write(source.join("plugin.json"), plugin_json.to_string()).unwrap();
+    store
+        .install_plugin_from_file(source.to_string_lossy().to_string(), None)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn install_pin_enable_and_uninstall_keep_the_index_in_sync() {
+    let dir = TempDir::new("index-sync");
+    let plugins = dir.0.join("plugins");
+    let store = PluginStore::new(&plugins);
+
+    install(&store, &dir, "usefultools-plugin-suite", &["alpha", "beta"]).await;
+    assert_eq!(indexed_ids(&plugins), ["alpha", "beta"]);
+
+    store
+        .set_plugin_pin("alpha".to_string(), Some("1.0.0".to_string()))
+        .await
+        .unwrap();
+    assert_eq!(
+        index_on_disk(&plugins)["plugins"]["alpha"]["pinnedVersion"],
+        "1.0.0"
+    );
+    store
+        .set_plugin_pin("alpha".to_string(), None)
+        .await
+        .unwrap();
+    assert!(index_on_disk(&plugins)["plugins"]["alpha"]["pinnedVersion"].is_null());
+
+    store
+        .set_plugin_enabled("beta".to_string(), false)
+        .await
+        .unwrap();
+    assert_eq!(index_on_disk(&plugins)["plugins"]["beta"]["enabled"], false);
+    store
+        .set_plugin_enabled("beta".to_string(), true)
+        .await
+        .unwrap();
+    assert_eq!(index_on_disk(&plugins)["plugins"]["beta"]["enabled"], true);
+
+    let removed = store
+        .uninstall_plugin("alpha".to_string(), false)
+        .await
+        .unwrap();
+    assert_eq!(removed, ["alpha"]);
+    assert_eq!(indexed_ids(&plugins), ["beta"]);
+
+    let installed = store.installed_plugins().await.unwrap();
+    let ids: Vec<&str> = installed.iter().map(|p| p.meta.id.as_str()).collect();
+    assert_eq!(ids, ["beta"]);
+}
+
+#[tokio::test]
+async fn corrupt_index_is_rebuilt_from_disk() {
+    let dir = TempDir::new("index-corrupt");
+    let plugins = dir.0.join("plugins");
+    let store = PluginStore::new(&plugins);
+    install(&store, &dir, "usefultools-plugin-demo", &["demo"]).await;
+
+    fs::write(plugins.join(INDEX_FILE), "{\"version\": 1, \"plugins\": {").unwrap();
+
+    let installed = store.installed_plugins().await.unwrap();
+    assert_eq!(installed.len(), 1);
+    assert_eq!(installed[0].meta.id, "demo");
+    assert_eq!(indexed_ids(&plugins), ["demo"]);
+    // 损坏的文件移到 .corrupt 保留，原因可通过修复查看
+    assert_eq!(
+        fs::read_to_string(plugins.join(CORRUPT_INDEX_FILE)).unwrap(),
+        "{\"version\": 1, \"plugins\": {"
+    );
+    let report = store.repair_index().await.unwrap();
+    assert_eq!(report.index_error.unwrap().code, ErrorCode::Parse);
+}
+
+#[tokio::test]
+async fn repair_reports_corrupt_index_and_plugin_directories() {
+    let dir = TempDir::new("index-repair");
+    let plugins = dir.0.join("plugins");
+    let store = PluginStore::new(&plugins);
+    install(&store, &dir, "usefultools-plugin-suite", &["alpha", "beta"]).await;
+
+    // 不是应用创建的不完整目录：恢复时保留，修复时报告
+    fs::create_dir_all(plugins.join("stray")).unwrap();
+    fs::write(plugins.join("stray/notes.txt"), "keep me").unwrap();
+    // 文件齐全但 meta.json 无法解析
+    fs::write(plugins.join("beta/meta.json"), "not json").unwrap();
+    fs::write(plugins.join(INDEX_FILE), "garbage").unwrap();
+
+    assert!(store.recover_interrupted_installs().removed.is_empty());
+    assert!(plugins.join("stray/notes.txt").is_file());
+
+    let report = store.repair_index().await.unwrap();
+
+    // 原索引无法读取，重建出的插件都算新增
+    assert_eq!(report.installed, 1);
+    assert_eq!(report.added, ["alpha"]);
+    assert!(report.dropped.is_empty());
+    assert_eq!(report.orphaned, ["stray"]);
+    assert_eq!(report.corrupt.len(), 1);
+    assert_eq!(report.corrupt[0].id, "beta");
+    assert_eq!(report.corrupt[0].error.code, ErrorCode::Parse);
+    let index_error = report.index_error.expect("损坏的索引应报告原因");
+    assert_eq!(index_error.code, ErrorCode::Parse);
+    assert_eq!(indexed_ids(&plugins), ["alpha"]);
+    assert_eq!(
+        fs::read_to_string(plugins.join(CORRUPT_INDEX_FILE)).unwrap(),
+        "garbage"
+    );
+}
+
+#[tokio::test]
+async fn repair_reports_plugins_dropped_from_the_index() {
+    let dir = TempDir::new("index-dropped");
+    let plugins = dir.0.join("plugins");
+    let store = PluginStore::new(&plugins);
+    install(&store, &dir, "usefultools-plugin-suite", &["alpha", "beta"]).await;
+
+    fs::remove_dir_all(plugins.join("alpha")).unwrap();
+
+    let report = store.repair_index().await.unwrap();
+
+    assert_eq!(report.dropped, ["alpha"]);
+    assert!(report.added.is_empty());
+    assert!(report.index_error.is_none());
+    assert_eq!(indexed_ids(&plugins), ["beta"]);
+}
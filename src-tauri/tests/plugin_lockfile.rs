@@ -0,0 +1,211 @@
+//! 插件锁文件：解析校验、差异计算与导入时的卸载检查
+
+mod common;
+
+use common::TempDir;
+use serde_json::json;
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::lockfile::{self, LockedPlugin, Lockfile};
+use usefultools_lib::plugin_manager::{LockfileImportResult, PluginStore};
+
+fn locked(id: &str, version: &str, integrity: Option<&str>) -> LockedPlugin {
+    LockedPlugin {
+        id: id.to_string(),
+        package_name: format!("usefultools-plugin-{id}"),
+        version: version.to_string(),
+        registry: Some("https://registry.npmjs.org".to_string()),
+        integrity: integrity.map(str::to_string),
+        origin: None,
+        tarball: None,
+    }
+}
+
+fn lockfile_json(version: u32, plugins: &[LockedPlugin]) -> String {
+    serde_json::json!({ "lockfileVersion": version, "plugins": plugins }).to_string()
+}
+
+// ── 解析 ────────────────────────────────────────────────────
+
+#[test]
+fn parse_accepts_current_version() {
+    let mut bundled = locked("demo", "1.0.0", Some("sha512-abc"));
+    bundled.tarball = Some("tarballs/abc.tgz".to_string());
+
+    let parsed = Lockfile::parse(&lockfile_json(
+        lockfile::LOCKFILE_VERSION,
+        &[bundled.clone(), locked("other", "2.0.0", None)],
+    ))
+    .unwrap();
+
+    assert_eq!(parsed.plugins, [bundled, locked("other", "2.0.0", None)]);
+}
+
+#[test]
+fn parse_rejects_newer_lockfile_version() {
+    let error = Lockfile::parse(&lockfile_json(lockfile::LOCKFILE_VERSION + 1, &[])).unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidInput, "{error}");
+}
+
+#[test]
+fn parse_rejects_unsafe_tarball_paths() {
+    for path in ["../outside.tgz", "/etc/passwd", "tarballs/../../x.tgz"] {
+        let mut plugin = locked("demo", "1.0.0", Some("sha512-abc"));
+        plugin.tarball = Some(path.to_string());
+
+        let error = Lockfile::parse(&lockfile_json(1, &[plugin])).unwrap_err();
+        assert_eq!(error.code, ErrorCode::InvalidInput, "{path}: {error}");
+    }
+}
+
+#[test]
+fn parse_rejects_bundled_tarball_without_integrity() {
+    let mut plugin = locked("demo", "1.0.0", None);
+    plugin.tarball = Some("tarballs/demo.tgz".to_string());
+
+    let error = Lockfile::parse(&lockfile_json(1, &[plugin])).unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidInput, "{error}");
+}
+
+#[test]
+fn parse_rejects_duplicate_ids_and_malformed_json() {
+    let duplicate = lockfile_json(
+        1,
+        &[locked("demo", "1.0.0", None), locked("demo", "2.0.0", None)],
+    );
+    let error = Lockfile::parse(&duplicate).unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidInput, "{error}");
+
+    let error = Lockfile::parse("{\"lockfileVersion\": 1").unwrap_err();
+    assert_eq!(error.code, ErrorCode::Parse, "{error}");
+}
+
+// ── 差异 ────────────────────────────────────────────────────
+
+#[test]
+fn diff_reports_added_removed_and_changed_plugins() {
+    let current = [
+        locked("same", "1.0.0", Some("sha512-a")),
+        locked("bumped", "1.0.0", None),
+        locked("rebuilt", "1.0.0", Some("sha512-old")),
+        locked("gone", "1.0.0", None),
+    ];
+    let target = [
+        locked("same", "1.0.0", Some("sha512-a")),
+        locked("bumped", "1.1.0", None),
+        locked("rebuilt", "1.0.0", Some("sha512-new")),
+        locked("new", "1.0.0", None),
+    ];
+
+    let diff = lockfile::diff(&current, &target);
+
+    assert_eq!(diff.added, [locked("new", "1.0.0", None)]);
+    assert_eq!(diff.removed, [locked("gone", "1.0.0", None)]);
+    let changed: Vec<(&str, &str, &str)> = diff
+        .changed
+        .iter()
+        .map(|c| {
+            (
+                c.id.as_str(),
+                c.from.version.as_str(),
+                c.to.version.as_str(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        changed,
+        [("bumped", "1.0.0", "1.1.0"), ("rebuilt", "1.0.0", "1.0.0")]
+    );
+}
+
+#[test]
+fn diff_ignores_integrity_recorded_on_one_side_only() {
+    let current = [locked("demo", "1.0.0", None)];
+    let target = [locked("demo", "1.0.0", Some("sha512-a"))];
+
+    let diff = lockfile::diff(&current, &target);
+
+    assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty());
+}
+
+// ── 导入 ────────────────────────────────────────────────────
+
+fn tool(id: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "version": "1.0.0",
+        "author": "tester",
+        "icon": "build",
+        "title": format!("Tool {id}"),
+        "subtitle": "test",
+        "description": "lockfile test plugin",
+        "bgColor": "bg-blue-500",
+        "categories": ["其他"],
+        "bundle": format!("dist/{id}.mjs"),
+    })
+}
+
+/// 安装一个包含 base 与依赖它的 app 的包，并导出锁文件
+async fn store_with_dependency(name: &str) -> (TempDir, PluginStore, Lockfile) {
+    let dir = TempDir::new(name);
+    let store = PluginStore::new(dir.0.join("plugins"));
+
+    let source = dir.0.join("source");
+    std::fs::create_dir_all(source.join("dist")).unwrap();
+    let package_json = json!({ "name": "usefultools-plugin-suite", "version": "1.0.0" });
+    std::fs::write(source.join("package.json"), package_json.to_string()).unwrap();
+    let mut app = tool("app");
+    app["dependencies"] = json!(["base"]);
+    let plugin_json = json!({ "plugins": [tool("base"), app] });
+    std::fs::write(source.join("plugin.json"), plugin_json.to_string()).unwrap();
+    for id in ["base", "app"] {
+        std::fs::write(source.join(format!("dist/{id}.mjs")), "export default 1").unwrap();
+    }
+    store
+        .install_plugin_from_file(source.to_string_lossy().to_string(), None)
+        .await
+        .unwrap();
+
+    let path = dir.0.join("exported.json");
+    let exported = store
+        .export_lockfile(path.to_string_lossy().to_string(), None)
+        .await
+        .unwrap();
+    (dir, store, exported)
+}
+
+async fn import(store: &PluginStore, dir: &TempDir, lockfile: &Lockfile) -> LockfileImportResult {
+    let path = dir.0.join("target.json");
+    std::fs::write(&path, serde_json::to_string(lockfile).unwrap()).unwrap();
+    store
+        .import_lockfile(path.to_string_lossy().to_string(), None)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn import_keeps_plugins_that_remaining_plugins_depend_on() {
+    let (dir, store, mut target) = store_with_dependency("lock-blocked").await;
+    target.plugins.retain(|p| p.id != "base");
+
+    let result = import(&store, &dir, &target).await;
+
+    assert!(result.removed.is_empty());
+    assert_eq!(result.failed.len(), 1);
+    assert_eq!(result.failed[0].plugin_id, "base");
+    assert_eq!(result.failed[0].error.code, ErrorCode::Dependency);
+    assert!(dir.0.join("plugins/base/meta.json").is_file());
+}
+
+#[tokio::test]
+async fn import_removes_a_plugin_together_with_its_dependents() {
+    let (dir, store, mut target) = store_with_dependency("lock-remove").await;
+    target.plugins.clear();
+
+    let result = import(&store, &dir, &target).await;
+
+    assert!(result.failed.is_empty(), "{:?}", result.failed);
+    let mut removed = result.removed.clone();
+    removed.sort();
+    assert_eq!(removed, ["app", "base"]);
+    assert!(store.installed_plugins().await.unwrap().is_empty());
+}
@@ -0,0 +1,271 @@
+//! plugin.json 校验：每条诊断的级别与 JSON 路径，以及诊断如何返回给调用方
+
+mod common;
+
+use common::TempDir;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::path::Path;
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::manifest::{self, Diagnostic, Severity};
+use usefultools_lib::plugin_manager::PluginStore;
+
+const PACKAGE: &str = "usefultools-plugin-demo";
+
+/// 字段齐全、没有任何诊断的单个工具条目
+fn tool(id: &str) -> Value {
+    json!({
+        "id": id,
+        "version": "1.0.0",
+        "author": "tester",
+        "icon": "build",
+        "title": format!("Tool {id}"),
+        "subtitle": "test",
+        "description": "manifest test plugin",
+        "bgColor": "bg-blue-500",
+        "categories": ["其他"],
+        "bundle": format!("dist/{id}.mjs"),
+    })
+}
+
+fn validate(plugin_json: &Value) -> manifest::ManifestReport {
+    manifest::validate(&plugin_json.to_string(), PACKAGE, &HashMap::new())
+}
+
+fn paths(diagnostics: &[Diagnostic]) -> Vec<(Severity, &str)> {
+    diagnostics
+        .iter()
+        .map(|d| (d.severity, d.path.as_str()))
+        .collect()
+}
+
+#[test]
+fn valid_entry_has_no_diagnostics() {
+    let report = validate(&tool("demo"));
+    assert!(report.diagnostics.is_empty(), "{:?}", report.diagnostics);
+    assert_eq!(report.entries.len(), 1);
+}
+
+#[test]
+fn typoed_field_is_a_warning_and_the_missing_field_an_error() {
+    let mut entry = tool("demo");
+    let description = entry["description"].take();
+    entry.as_object_mut().unwrap().remove("description");
+    entry["descripton"] = description;
+
+    let report = validate(&entry);
+
+    assert_eq!(
+        paths(&report.diagnostics),
+        [
+            (Severity::Warning, "$.descripton"),
+            (Severity::Error, "$.description"),
+        ]
+    );
+    assert!(report.diagnostics[0].message.contains("description"));
+    assert!(report.entries.is_empty());
+}
+
+#[test]
+fn duplicate_id_is_an_error_on_the_second_entry() {
+    let report = validate(&json!({
+        "plugins": [tool("demo"), tool("demo")],
+    }));
+
+    assert_eq!(
+        paths(&report.diagnostics),
+        [(Severity::Error, "$.plugins[1].id")]
+    );
+    assert_eq!(report.entries.len(), 1);
+}
+
+#[test]
+fn id_used_by_another_installed_package_is_an_error() {
+    let installed = HashMap::from([("demo".to_string(), "usefultools-plugin-other".to_string())]);
+    let content = tool("demo").to_string();
+
+    let report = manifest::validate(&content, PACKAGE, &installed);
+    assert_eq!(paths(&report.diagnostics), [(Severity::Error, "$.id")]);
+
+    // 同一个包的新版本不算冲突
+    let report = manifest::validate(&content, "usefultools-plugin-other", &installed);
+    assert!(report.diagnostics.is_empty());
+}
+
+#[test]
+fn empty_title_is_only_a_warning() {
+    for title in ["", "   ", "<br/>"] {
+        let mut entry = tool("demo");
+        entry["title"] = json!(title);
+
+        let report = validate(&entry);
+        assert_eq!(
+            paths(&report.diagnostics),
+            [(Severity::Warning, "$.title")],
+            "{title:?}"
+        );
+        assert_eq!(report.entries.len(), 1);
+    }
+}
+
+#[test]
+fn bg_color_without_a_background_class_is_only_a_warning() {
+    for color in ["blue", "text-white", "bg-red-500;"] {
+        let mut entry = tool("demo");
+        entry["bgColor"] = json!(color);
+
+        let report = validate(&entry);
+        assert_eq!(
+            paths(&report.diagnostics),
+            [(Severity::Warning, "$.bgColor")],
+            "{color:?}"
+        );
+        assert_eq!(report.entries.len(), 1);
+    }
+}
+
+#[test]
+fn bundle_outside_the_package_is_an_error() {
+    for bundle in ["../demo.mjs", "/etc/demo.mjs"] {
+        let mut entry = tool("demo");
+        entry["bundle"] = json!(bundle);
+
+        let report = validate(&entry);
+        assert_eq!(
+            paths(&report.diagnostics),
+            [(Severity::Error, "$.bundle")],
+            "{bundle:?}"
+        );
+        assert!(report.entries.is_empty());
+    }
+}
+
+/// 校验加入前就已发布的插件：展示与声明上的问题都只是警告，插件照常可用
+#[test]
+fn existing_style_manifest_still_loads() {
+    let report = validate(&json!({
+        "id": "legacy",
+        "version": "0.3",
+        "author": "",
+        "icon": "Build",
+        "title": "",
+        "subtitle": "old tool",
+        "description": "published before manifest validation",
+        "bgColor": "#ff8800",
+        "textColor": "white",
+        "categories": ["工具", ""],
+        "requires": ["storage"],
+        "bundle": "index.js",
+    }));
+
+    assert!(!report.has_errors(), "{:?}", report.diagnostics);
+    assert_eq!(report.diagnostics.len(), 9, "{:?}", report.diagnostics);
+    let entries = report.into_result().unwrap();
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].id, "legacy");
+}
+
+#[test]
+fn unknown_category_is_only_a_warning() {
+    let mut entry = tool("demo");
+    entry["categories"] = json!(["其他", "奇怪的分类"]);
+
+    let report = validate(&entry);
+
+    assert_eq!(
+        paths(&report.diagnostics),
+        [(Severity::Warning, "$.categories[1]")]
+    );
+    assert_eq!(report.entries.len(), 1);
+}
+
+#[test]
+fn wrong_types_in_a_multi_tool_entry_are_reported_by_path() {
+    let mut broken = tool("broken");
+    broken["categories"] = json!("其他");
+    broken["requires"] = json!(["http", 1, "storage"]);
+
+    let report = validate(&json!({
+        "plugins": [tool("demo"), broken, "not an object"],
+    }));
+
+    assert_eq!(
+        paths(&report.diagnostics),
+        [
+            (Severity::Error, "$.plugins[2]"),
+            (Severity::Error, "$.plugins[1].categories"),
+            (Severity::Error, "$.plugins[1].requires[1]"),
+            (Severity::Warning, "$.plugins[1].requires[2]"),
+        ]
+    );
+    let ids: Vec<&str> = report.entries.iter().map(|e| e.id.as_str()).collect();
+    assert_eq!(ids, ["demo"]);
+}
+
+#[test]
+fn into_result_lists_every_error_with_its_path() {
+    let mut entry = tool("demo");
+    entry["id"] = json!("Demo Tool");
+    entry["bundle"] = json!("../demo.mjs");
+    entry["categories"] = json!(["奇怪的分类"]);
+
+    let error = validate(&entry).into_result().unwrap_err();
+
+    assert_eq!(error.code, ErrorCode::InvalidManifest);
+    assert!(error.message.contains("$.id"), "{error}");
+    assert!(error.message.contains("$.bundle"), "{error}");
+    assert!(!error.message.contains("$.categories"), "{error}");
+    // context 中带有全部诊断，包括警告
+    assert_eq!(
+        paths(&error.context.diagnostics),
+        [
+            (Severity::Error, "$.id"),
+            (Severity::Warning, "$.categories[0]"),
+            (Severity::Error, "$.bundle"),
+        ]
+    );
+}
+
+// ── 返回给调用方 ────────────────────────────────────────────
+
+fn write_plugin_dir(dir: &Path, entry: &Value) {
+    std::fs::create_dir_all(dir.join("dist")).unwrap();
+    let package_json = json!({ "name": PACKAGE, "version": "1.0.0" });
+    std::fs::write(dir.join("package.json"), package_json.to_string()).unwrap();
+    std::fs::write(dir.join("plugin.json"), entry.to_string()).unwrap();
+    std::fs::write(dir.join("dist/demo.mjs"), "export default 1").unwrap();
+}
+
+#[tokio::test]
+async fn local_plugin_json_returns_warnings_or_diagnostics() {
+    let dir = TempDir::new("manifest-local");
+    let store = PluginStore::new(dir.0.join("plugins"));
+
+    let mut entry = tool("demo");
+    entry["categories"] = json!(["奇怪的分类"]);
+    write_plugin_dir(&dir.0.join("ok"), &entry);
+    let loaded = store
+        .read_local_plugin_json(dir.0.join("ok").to_string_lossy().to_string())
+        .await
+        .unwrap();
+    assert_eq!(loaded.plugins.len(), 1);
+    assert_eq!(
+        paths(&loaded.warnings),
+        [(Severity::Warning, "$.categories[0]")]
+    );
+
+    entry["bundle"] = json!("../demo.mjs");
+    write_plugin_dir(&dir.0.join("bad"), &entry);
+    let error = store
+        .read_local_plugin_json(dir.0.join("bad").to_string_lossy().to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidManifest);
+    assert_eq!(
+        paths(&error.context.diagnostics),
+        [
+            (Severity::Warning, "$.categories[0]"),
+            (Severity::Error, "$.bundle"),
+        ]
+    );
+}
@@ -0,0 +1,284 @@
+//! 插件作者流程：打包插件目录、检查 tarball、从 .tgz 安装、按 npm 协议发布，以及命令行工具
+
+mod common;
+
+use base64::Engine;
+use common::{MockServer, Request, Response, TempDir};
+use serde_json::{json, Value};
+use std::path::Path;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::pack::{self, Package};
+use usefultools_lib::plugin_manager::PluginStore;
+
+const PACKAGE: &str = "usefultools-plugin-suite";
+
+/// 含两个工具与 README 的插件目录；notes.txt 不应被打包
+///
+/// plugin.json 中的 bundle 路径为 `{bundle_dir}/{id}.mjs`，文件始终写在 dist 下。
+fn write_suite(dir: &Path, bundle_dir: &str) {
+    std::fs::create_dir_all(dir.join("dist")).unwrap();
+    let package_json = json!({ "name": PACKAGE, "version": "1.0.0", "description": "suite" });
+    std::fs::write(dir.join("package.json"), package_json.to_string()).unwrap();
+    let tools: Vec<Value> = ["base", "app"]
+        .iter()
+        .map(|id| {
+            json!({
+                "id": id,
+                "version": "1.0.0",
+                "author": "tester",
+                "icon": "build",
+                "title": format!("Tool {id}"),
+                "subtitle": "test",
+                "description": "pack test plugin",
+                "bgColor": "bg-blue-500",
+                "categories": ["其他"],
+                "bundle": format!("{bundle_dir}/{id}.mjs"),
+            })
+        })
+        .collect();
+    let plugin_json = json!({ "plugins": tools });
+    std::fs::write(dir.join("plugin.json"), plugin_json.to_string()).unwrap();
+    for id in ["base", "app"] {
+        std::fs::write(
+            dir.join(format!("dist/{id}.mjs")),
+            format!("export default '{id}';"),
+        )
+        .unwrap();
+    }
+    std::fs::write(dir.join("README.md"), "# suite").unwrap();
+    std::fs::write(dir.join("notes.txt"), "not packed").unwrap();
+}
+
+/// 接受第一次发布、之后返回 409 的 registry，记录收到的发布文档
+fn publish_registry() -> (MockServer, Arc<Mutex<Vec<Value>>>) {
+    let published = Arc::new(Mutex::new(Vec::new()));
+    let documents = published.clone();
+    let server = MockServer::with_handler(move |request: &Request| {
+        if request.method != "PUT" {
+            return None;
+        }
+        let mut documents = documents.lock().unwrap();
+        if !documents.is_empty() {
+            return Some(Response::json(409, r#"{"error":"version already exists"}"#));
+        }
+        documents.push(serde_json::from_slice(&request.body).unwrap());
+        Some(Response::json(201, r#"{"ok":true}"#))
+    });
+    (server, published)
+}
+
+fn client() -> reqwest::Client {
+    reqwest::Client::builder().no_proxy().build().unwrap()
+}
+
+// ── 打包 ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn pack_includes_declared_files_and_is_reproducible() {
+    let dir = TempDir::new("pack");
+    write_suite(&dir.0, "dist");
+
+    let package = pack::pack(&dir.0).await.unwrap();
+
+    assert_eq!(
+        (package.name.as_str(), package.version.as_str()),
+        (PACKAGE, "1.0.0")
+    );
+    assert!(package.manifest.diagnostics.is_empty());
+    let paths: Vec<&str> = package.files.iter().map(|f| f.path.as_str()).collect();
+    assert_eq!(
+        paths,
+        [
+            "package/package.json",
+            "package/plugin.json",
+            "package/dist/base.mjs",
+            "package/dist/app.mjs",
+            "package/README.md",
+        ]
+    );
+    assert_eq!(package.file_name(), format!("{PACKAGE}-1.0.0.tgz"));
+
+    // 打包结果与时间无关，从 tarball 读回的信息与打包时一致
+    let again = pack::pack(&dir.0).await.unwrap();
+    assert_eq!(again.bytes, package.bytes);
+    let inspected = Package::from_tarball(package.bytes.clone()).unwrap();
+    assert_eq!(inspected.integrity, package.integrity);
+    assert_eq!(inspected.manifest.entries.len(), 2);
+}
+
+#[tokio::test]
+async fn pack_rejects_invalid_plugin_json() {
+    let dir = TempDir::new("pack-invalid");
+    write_suite(&dir.0, "../dist");
+
+    let error = pack::pack(&dir.0).await.unwrap_err();
+
+    assert_eq!(error.code, ErrorCode::InvalidManifest);
+    assert!(error.message.contains("$.plugins[0].bundle"), "{error}");
+    assert_eq!(error.context.diagnostics.len(), 2);
+}
+
+#[tokio::test]
+async fn packed_tarball_installs_from_file() {
+    let dir = TempDir::new("pack-install");
+    let source = dir.0.join("source");
+    write_suite(&source, "dist");
+    let package = pack::pack(&source).await.unwrap();
+    let tgz = dir.0.join(package.file_name());
+    std::fs::write(&tgz, &package.bytes).unwrap();
+
+    let store = PluginStore::new(dir.0.join("plugins"));
+    let installed = store
+        .install_plugin_from_file(tgz.to_string_lossy().to_string(), None)
+        .await
+        .unwrap();
+
+    let ids: Vec<&str> = installed.iter().map(|p| p.meta.id.as_str()).collect();
+    assert_eq!(ids, ["base", "app"]);
+    assert!(installed.iter().all(|p| p.meta.package_name == PACKAGE));
+    assert_eq!(
+        store.read_plugin_bundle("app".to_string()).await.unwrap(),
+        "export default 'app';"
+    );
+}
+
+// ── 发布 ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn publish_sends_the_npm_publish_document() {
+    let (registry, published) = publish_registry();
+    let dir = TempDir::new("publish");
+    write_suite(&dir.0, "dist");
+    let package = pack::pack(&dir.0).await.unwrap();
+
+    let tarball_url = pack::publish(&client(), &registry.url, &package, "beta", Some("tok"))
+        .await
+        .unwrap();
+
+    let file_name = package.file_name();
+    assert_eq!(
+        tarball_url,
+        format!("{}/{PACKAGE}/-/{file_name}", registry.url)
+    );
+    let put = &registry.requests()[0];
+    assert_eq!(put.path(), format!("/{PACKAGE}"));
+    assert_eq!(put.headers["authorization"], "Bearer tok");
+
+    let document = published.lock().unwrap()[0].clone();
+    assert_eq!(document["dist-tags"]["beta"], "1.0.0");
+    assert_eq!(
+        document["versions"]["1.0.0"]["dist"]["integrity"],
+        package.integrity
+    );
+    let data = document["_attachments"][&file_name]["data"]
+        .as_str()
+        .unwrap();
+    let bytes = base64::engine::general_purpose::STANDARD
+        .decode(data)
+        .unwrap();
+    assert_eq!(bytes, package.bytes);
+
+    // 同一版本不能重复发布
+    let error = pack::publish(&client(), &registry.url, &package, "latest", None)
+        .await
+        .unwrap_err();
+    assert_eq!(error.context.status, Some(409));
+    assert_eq!(error.context.package.as_deref(), Some(PACKAGE));
+}
+
+#[tokio::test]
+async fn scoped_package_is_published_under_the_escaped_name() {
+    let (registry, published) = publish_registry();
+    let dir = TempDir::new("publish-scoped");
+    write_suite(&dir.0, "dist");
+    let package_json = json!({ "name": format!("@corp/{PACKAGE}"), "version": "1.0.0" });
+    std::fs::write(dir.0.join("package.json"), package_json.to_string()).unwrap();
+    let package = pack::pack(&dir.0).await.unwrap();
+    assert_eq!(package.file_name(), format!("corp-{PACKAGE}-1.0.0.tgz"));
+
+    let tarball_url = pack::publish(&client(), &registry.url, &package, "latest", None)
+        .await
+        .unwrap();
+
+    // registry 上的文件名不含 scope，包名中的 / 转义为 %2f
+    let file_name = format!("{PACKAGE}-1.0.0.tgz");
+    assert_eq!(
+        tarball_url,
+        format!("{}/@corp%2f{PACKAGE}/-/{file_name}", registry.url)
+    );
+    assert_eq!(registry.requests()[0].path(), format!("/@corp%2f{PACKAGE}"));
+    let document = published.lock().unwrap()[0].clone();
+    assert_eq!(document["name"], format!("@corp/{PACKAGE}"));
+    assert_eq!(
+        document["versions"]["1.0.0"]["dist"]["tarball"],
+        tarball_url
+    );
+    let attachments: Vec<&String> = document["_attachments"]
+        .as_object()
+        .unwrap()
+        .keys()
+        .collect();
+    assert_eq!(attachments, [&file_name]);
+}
+
+// ── 命令行 ──────────────────────────────────────────────────
+
+fn cli(args: &[&str]) -> std::process::Output {
+    Command::new(env!("CARGO_BIN_EXE_usefultools-plugin"))
+        .args(args)
+        .env("NO_PROXY", "127.0.0.1")
+        .env_remove("NPM_TOKEN")
+        .output()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn cli_validates_packs_and_publishes() {
+    let (registry, published) = publish_registry();
+    let dir = TempDir::new("pack-cli");
+    let source = dir.0.join("source");
+    write_suite(&source, "dist");
+    let source = source.to_string_lossy().to_string();
+    let out = dir.0.join("out");
+
+    let validated = cli(&["validate", &source]);
+    assert!(validated.status.success(), "{validated:?}");
+
+    let packed = cli(&["pack", &source, "--out", &out.to_string_lossy()]);
+    assert!(packed.status.success(), "{packed:?}");
+    let tgz = out.join(format!("{PACKAGE}-1.0.0.tgz"));
+    let expected = pack::pack(Path::new(&source)).await.unwrap();
+    assert_eq!(std::fs::read(&tgz).unwrap(), expected.bytes);
+
+    // 发布使用 plugins 目录中的网络设置
+    let plugins = dir.0.join("plugins");
+    std::fs::create_dir_all(&plugins).unwrap();
+    let config = json!({
+        "registry": registry.url,
+        "network": { "userAgent": "publish-test/1.0" },
+    });
+    std::fs::write(plugins.join("config.json"), config.to_string()).unwrap();
+    let publish = [
+        "publish",
+        &tgz.to_string_lossy(),
+        "--registry",
+        &registry.url,
+        "--plugins-dir",
+        &plugins.to_string_lossy(),
+    ];
+    let output = cli(&publish);
+    assert!(output.status.success(), "{output:?}");
+    assert_eq!(published.lock().unwrap().len(), 1);
+    assert_eq!(
+        registry.requests()[0].headers["user-agent"],
+        "publish-test/1.0"
+    );
+
+    let broken = dir.0.join("broken");
+    write_suite(&broken, "../dist");
+    let invalid = cli(&["validate", &broken.to_string_lossy()]);
+    assert!(!invalid.status.success());
+    assert!(String::from_utf8_lossy(&invalid.stderr).contains("$.plugins[0].bundle"));
+}
@@ -0,0 +1,587 @@
+//! PluginStore 对接 npm registry 的集成测试
+//!
+//! 每个测试启动一个只在本机监听的模拟 registry，按 npm 的接口返回搜索结果、
+//! 包详情与 tarball，覆盖搜索、安装、404 与格式错误的 JSON。
+
+mod common;
+
+use common::{append_file, build_tgz, MockServer, Response, TempDir};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::manifest::Severity;
+use usefultools_lib::plugin_manager::pack::Package;
+use usefultools_lib::plugin_manager::tarball::MAX_TARBALL_SIZE;
+use usefultools_lib::plugin_manager::{InstallProgress, PluginConfig, PluginStore, UpdateKind};
+
+/// 模拟的 npm registry：包详情与 tarball 按 npm 的地址提供
+struct Registry {
+    server: MockServer,
+}
+
+impl Registry {
+    fn start() -> Self {
+        Self {
+            server: MockServer::start(),
+        }
+    }
+
+    fn url(&self) -> &str {
+        &self.server.url
+    }
+
+    /// 发布包的各个版本，latest 指向最后一个
+    fn publish(&self, name: &str, packages: &[&Package]) {
+        let mut versions = serde_json::Map::new();
+        for package in packages {
+            let path = format!("/{name}/-/{}", package.file_name());
+            self.server.route(
+                &path,
+                Response::new(200, "application/octet-stream", package.bytes.clone()),
+            );
+            versions.insert(
+                package.version.clone(),
+                json!({
+                    "name": name,
+                    "version": package.version,
+                    "dist": {
+                        "tarball": format!("{}{path}", self.url()),
+                        "integrity": package.integrity,
+                    },
+                }),
+            );
+        }
+        let latest = &packages.last().unwrap().version;
+        self.server.json(
+            &format!("/{name}"),
+            json!({ "name": name, "dist-tags": { "latest": latest }, "versions": versions }),
+        );
+    }
+
+    fn search(&self, names: &[&str]) {
+        let objects: Vec<_> = names
+            .iter()
+            .map(|name| json!({ "package": { "name": name, "version": "1.0.0" } }))
+            .collect();
+        self.server.json(
+            "/-/v1/search",
+            json!({ "objects": objects, "total": names.len() }),
+        );
+    }
+}
+
+/// 字段齐全的单个工具条目
+fn tool_json(id: &str, version: &str) -> Value {
+    json!({
+        "id": id,
+        "version": version,
+        "author": "tester",
+        "icon": "build",
+        "title": format!("Tool {id}"),
+        "subtitle": "test",
+        "description": "registry test plugin",
+        "bgColor": "bg-blue-500",
+        "categories": ["其他"],
+        "bundle": format!("dist/{id}.mjs"),
+    })
+}
+
+/// 按 npm pack 的布局构造只含一个工具的插件包
+fn plugin_package(id: &str, version: &str) -> Package {
+    package_with(&tool_json(id, version))
+}
+
+/// 同上，plugin.json 由调用方给出
+fn package_with(plugin_json: &Value) -> Package {
+    let id = plugin_json["id"].as_str().unwrap();
+    let version = plugin_json["version"].as_str().unwrap();
+    let name = format!("usefultools-plugin-{id}");
+    let package_json = json!({ "name": name, "version": version });
+    let bytes = build_tgz(|b| {
+        append_file(
+            b,
+            "package/package.json",
+            package_json.to_string().as_bytes(),
+        );
+        append_file(b, "package/plugin.json", plugin_json.to_string().as_bytes());
+        let bundle = format!("export default '{id}@{version}';");
+        append_file(b, &format!("package/dist/{id}.mjs"), bundle.as_bytes());
+    });
+    Package::from_tarball(bytes).unwrap()
+}
+
+/// 指向模拟 registry 的 PluginStore（不经过系统代理，不重试）
+async fn store_for(registry: &Registry, dir: &TempDir) -> PluginStore {
+    let http = reqwest::Client::builder().no_proxy().build().unwrap();
+    let store = PluginStore::new(&dir.0).with_http_client(http);
+    let mut config = PluginConfig {
+        registry: registry.url().to_string(),
+        ..Default::default()
+    };
+    config.network.retries = 0;
+    store.set_plugin_config(config).await.unwrap();
+    store
+}
+
+// ── 搜索与包详情 ────────────────────────────────────────────
+
+#[tokio::test]
+async fn search_lists_plugins_from_matching_packages() {
+    let registry = Registry::start();
+    let dir = TempDir::new("search");
+    let store = store_for(&registry, &dir).await;
+
+    registry.publish(
+        "usefultools-plugin-demo",
+        &[&plugin_package("demo", "1.0.0")],
+    );
+    registry.search(&["usefultools-plugin-demo", "left-pad"]);
+
+    let result = store.fetch_registry(true).await.unwrap();
+
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+    assert_eq!(result.plugins.len(), 1);
+    let plugin = &result.plugins[0];
+    assert_eq!(plugin.id, "demo");
+    assert_eq!(plugin.version, "1.0.0");
+    assert_eq!(plugin.package_name, "usefultools-plugin-demo");
+    assert_eq!(plugin.registry.as_deref(), Some(registry.url()));
+    // 不符合命名规则的包不会被请求
+    assert_eq!(registry.server.count("/left-pad"), 0);
+}
+
+#[tokio::test]
+async fn package_detail_lists_versions_newest_first() {
+    let registry = Registry::start();
+    let dir = TempDir::new("versions");
+    let store = store_for(&registry, &dir).await;
+
+    let v1 = plugin_package("demo", "1.0.0");
+    let v2 = plugin_package("demo", "1.2.0");
+    registry.publish("usefultools-plugin-demo", &[&v1, &v2]);
+
+    let versions = store
+        .list_plugin_versions("usefultools-plugin-demo".to_string(), None)
+        .await
+        .unwrap();
+    let numbers: Vec<&str> = versions.iter().map(|v| v.version.as_str()).collect();
+    assert_eq!(numbers, ["1.2.0", "1.0.0"]);
+
+    let plugins = store
+        .fetch_package_by_name("usefultools-plugin-demo".to_string())
+        .await
+        .unwrap();
+    assert_eq!(plugins[0].version, "1.2.0");
+}
+
+// ── tarball ─────────────────────────────────────────────────
+
+#[tokio::test]
+async fn install_writes_bundle_from_tarball() {
+    let registry = Registry::start();
+    let dir = TempDir::new("install");
+    let store = store_for(&registry, &dir).await;
+
+    let demo = plugin_package("demo", "1.0.0");
+    registry.publish("usefultools-plugin-demo", &[&demo]);
+    registry.search(&["usefultools-plugin-demo"]);
+
+    let plugin = store.fetch_registry(true).await.unwrap().plugins.remove(0);
+    let info = store.install_plugin(plugin).await.unwrap();
+
+    assert_eq!(
+        info.meta.integrity.as_deref(),
+        Some(demo.integrity.as_str())
+    );
+    let bundle = std::fs::read_to_string(&info.local_bundle_path).unwrap();
+    assert_eq!(bundle, "export default 'demo@1.0.0';");
+
+    let installed = store.installed_plugins().await.unwrap();
+    assert_eq!(installed.len(), 1);
+    assert_eq!(installed[0].meta.id, "demo");
+
+    let removed = store
+        .uninstall_plugin("demo".to_string(), false)
+        .await
+        .unwrap();
+    assert_eq!(removed, ["demo"]);
+    assert!(store.installed_plugins().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn cache_write_failure_is_reported_without_failing_the_install() {
+    let registry = Registry::start();
+    let dir = TempDir::new("cache-warning");
+    // 缓存目录的位置被文件占用，无法写入缓存
+    std::fs::write(dir.0.join(".cache"), "").unwrap();
+    let warnings = Arc::new(Mutex::new(Vec::new()));
+    let seen = warnings.clone();
+    let store =
+        store_for(&registry, &dir)
+            .await
+            .with_progress(move |progress: &InstallProgress| {
+                seen.lock().unwrap().extend(progress.warning.clone());
+            });
+
+    registry.publish(
+        "usefultools-plugin-demo",
+        &[&plugin_package("demo", "1.0.0")],
+    );
+    registry.search(&["usefultools-plugin-demo"]);
+    let plugin = store.fetch_registry(true).await.unwrap().plugins.remove(0);
+    store.install_plugin(plugin).await.unwrap();
+
+    let warnings = warnings.lock().unwrap();
+    assert_eq!(warnings.len(), 1, "{warnings:?}");
+    assert_eq!(warnings[0].code, ErrorCode::Io);
+    assert_eq!(warnings[0].context.plugin_id.as_deref(), Some("demo"));
+}
+
+#[tokio::test]
+async fn tarball_with_wrong_integrity_is_rejected() {
+    let registry = Registry::start();
+    let dir = TempDir::new("integrity");
+    let store = store_for(&registry, &dir).await;
+
+    let demo = plugin_package("demo", "1.0.0");
+    registry.publish("usefultools-plugin-demo", &[&demo]);
+    // tarball 地址返回的内容与包详情中的 integrity 不符
+    let other = plugin_package("demo", "9.9.9");
+    registry.server.route(
+        &format!("/usefultools-plugin-demo/-/{}", demo.file_name()),
+        Response::new(200, "application/octet-stream", other.bytes),
+    );
+
+    let error = store
+        .fetch_package_by_name("usefultools-plugin-demo".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::Integrity, "{error}");
+    assert!(error.message.contains("sha512"), "{error}");
+}
+
+#[tokio::test]
+async fn oversized_tarball_is_rejected() {
+    let registry = Registry::start();
+    let dir = TempDir::new("oversized");
+    let store = store_for(&registry, &dir).await;
+
+    let demo = plugin_package("demo", "1.0.0");
+    registry.publish("usefultools-plugin-demo", &[&demo]);
+    registry.search(&["usefultools-plugin-demo"]);
+    let plugin = store.fetch_registry(true).await.unwrap().plugins.remove(0);
+    // 刷新注册表时缓存了正常的 tarball，清除后安装才会重新下载
+    std::fs::remove_dir_all(dir.0.join(".cache")).unwrap();
+    let huge = vec![0u8; MAX_TARBALL_SIZE as usize + 1];
+    registry.server.route(
+        &format!("/usefultools-plugin-demo/-/{}", demo.file_name()),
+        Response::new(200, "application/octet-stream", huge),
+    );
+
+    let error = store.install_plugin(plugin).await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidPackage, "{error}");
+    assert!(error.context.url.is_some());
+    assert!(store.installed_plugins().await.unwrap().is_empty());
+
+    // 本地 .tgz 同样按文件大小拒绝，不读入内存
+    let tgz = dir.0.join("huge.tgz");
+    let file = std::fs::File::create(&tgz).unwrap();
+    file.set_len(MAX_TARBALL_SIZE + 1).unwrap();
+    let error = store
+        .install_plugin_from_file(tgz.to_string_lossy().to_string(), None)
+        .await
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidPackage, "{error}");
+}
+
+// ── 版本锁定 ────────────────────────────────────────────────
+
+#[tokio::test]
+async fn pin_accepts_only_published_versions() {
+    let registry = Registry::start();
+    let dir = TempDir::new("pin");
+    let store = store_for(&registry, &dir).await;
+
+    let v1 = plugin_package("demo", "1.0.0");
+    let v2 = plugin_package("demo", "1.2.0");
+    registry.publish("usefultools-plugin-demo", &[&v1, &v2]);
+    registry.search(&["usefultools-plugin-demo"]);
+    let plugin = store.fetch_registry(true).await.unwrap().plugins.remove(0);
+    store.install_plugin(plugin).await.unwrap();
+
+    let pin = |version: &str| store.set_plugin_pin("demo".to_string(), Some(version.to_string()));
+    let error = pin("latest").await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::InvalidInput, "{error}");
+    let error = pin("3.0.0").await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::NotFound, "{error}");
+    assert_eq!(error.context.plugin_id.as_deref(), Some("demo"));
+
+    assert_eq!(
+        store.installed_plugins().await.unwrap()[0].meta.version,
+        "1.2.0"
+    );
+
+    // 锁定其他版本时先安装该版本
+    pin("1.0.0").await.unwrap();
+    let installed = store.installed_plugins().await.unwrap();
+    assert_eq!(installed[0].pinned_version.as_deref(), Some("1.0.0"));
+    assert_eq!(installed[0].meta.version, "1.0.0");
+    let bundle = std::fs::read_to_string(&installed[0].local_bundle_path).unwrap();
+    assert_eq!(bundle, "export default 'demo@1.0.0';");
+
+    store
+        .set_plugin_pin("demo".to_string(), None)
+        .await
+        .unwrap();
+    let installed = store.installed_plugins().await.unwrap();
+    assert_eq!(installed[0].pinned_version, None);
+}
+
+// ── 更新 ────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn updates_match_the_installed_registry_and_skip_local_files() {
+    let registry = Registry::start();
+    let dir = TempDir::new("updates");
+    let store = store_for(&registry, &dir).await;
+
+    registry.publish(
+        "usefultools-plugin-demo",
+        &[&plugin_package("demo", "1.0.0")],
+    );
+    registry.search(&["usefultools-plugin-demo"]);
+    let plugin = store.fetch_registry(true).await.unwrap().plugins.remove(0);
+    store.install_plugin(plugin).await.unwrap();
+    // 从本地文件安装的插件，registry 中同名包有更高的版本
+    let local = plugin_package("local", "1.0.0");
+    let tgz = dir.0.join(local.file_name());
+    std::fs::write(&tgz, &local.bytes).unwrap();
+    store
+        .install_plugin_from_file(tgz.to_string_lossy().to_string(), None)
+        .await
+        .unwrap();
+
+    let v1 = plugin_package("demo", "1.0.0");
+    let v2 = plugin_package("demo", "2.0.0");
+    registry.publish("usefultools-plugin-demo", &[&v1, &v2]);
+    registry.publish(
+        "usefultools-plugin-local",
+        &[&plugin_package("local", "1.1.0")],
+    );
+    registry.search(&["usefultools-plugin-demo", "usefultools-plugin-local"]);
+    store.fetch_registry(true).await.unwrap();
+
+    let updates = store.check_updates().await.unwrap();
+    let found: Vec<_> = updates
+        .iter()
+        .map(|u| {
+            (
+                u.plugin.id.as_str(),
+                u.current_version.as_str(),
+                u.plugin.version.as_str(),
+                u.kind,
+            )
+        })
+        .collect();
+    assert_eq!(found, [("demo", "1.0.0", "2.0.0", UpdateKind::Major)]);
+
+    // 换到另一个 registry 后，其中的同名包不算已安装插件的更新
+    let mirror = Registry::start();
+    let v3 = plugin_package("demo", "3.0.0");
+    mirror.publish("usefultools-plugin-demo", &[&v3]);
+    mirror.search(&["usefultools-plugin-demo"]);
+    let mut config = store.plugin_config().await;
+    config.registry = mirror.url().to_string();
+    store.set_plugin_config(config).await.unwrap();
+    assert_eq!(store.fetch_registry(true).await.unwrap().plugins.len(), 1);
+
+    assert!(store.check_updates().await.unwrap().is_empty());
+}
+
+// ── 404 ─────────────────────────────────────────────────────
+
+#[tokio::test]
+async fn missing_package_is_an_error() {
+    let registry = Registry::start();
+    let dir = TempDir::new("missing");
+    let store = store_for(&registry, &dir).await;
+
+    let error = store
+        .fetch_package_by_name("usefultools-plugin-missing".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::NotFound, "{error}");
+    assert_eq!(error.context.status, Some(404));
+    assert_eq!(
+        error.context.package.as_deref(),
+        Some("usefultools-plugin-missing")
+    );
+}
+
+#[tokio::test]
+async fn missing_tarball_is_an_error() {
+    let registry = Registry::start();
+    let dir = TempDir::new("missing-tarball");
+    let store = store_for(&registry, &dir).await;
+
+    let demo = plugin_package("demo", "1.0.0");
+    registry.publish("usefultools-plugin-demo", &[&demo]);
+    registry.server.route(
+        &format!("/usefultools-plugin-demo/-/{}", demo.file_name()),
+        Response::json(404, "{}"),
+    );
+
+    let error = store
+        .fetch_package_by_name("usefultools-plugin-demo".to_string())
+        .await
+        .unwrap_err();
+    assert_eq!(error.code, ErrorCode::NotFound, "{error}");
+    assert_eq!(error.context.status, Some(404));
+    assert!(error.context.url.unwrap().ends_with(&demo.file_name()));
+}
+
+// ── 格式错误的 JSON ─────────────────────────────────────────
+
+#[tokio::test]
+async fn malformed_search_response_fails_without_cache() {
+    let registry = Registry::start();
+    let dir = TempDir::new("bad-search");
+    let store = store_for(&registry, &dir).await;
+
+    registry
+        .server
+        .route("/-/v1/search", Response::json(200, "{\"objects\": ["));
+
+    let error = store.fetch_registry(true).await.unwrap_err();
+    assert_eq!(error.code, ErrorCode::Parse, "{error}");
+}
+
+#[tokio::test]
+async fn incomplete_search_is_reported_with_partial_results() {
+    // 第一页之后的搜索请求都失败
+    let registry = Registry {
+        server: MockServer::with_handler(|request| {
+            (request.path() == "/-/v1/search" && request.query("from") != Some("0"))
+                .then(|| Response::new(503, "text/plain", Vec::new()))
+        }),
+    };
+    let dir = TempDir::new("partial-search");
+    let store = store_for(&registry, &dir).await;
+
+    registry.publish(
+        "usefultools-plugin-demo",
+        &[&plugin_package("demo", "1.0.0")],
+    );
+    registry.server.json(
+        "/-/v1/search",
+        json!({
+            "objects": [{ "package": { "name": "usefultools-plugin-demo", "version": "1.0.0" } }],
+            "total": 1000,
+        }),
+    );
+
+    let result = store.fetch_registry(true).await.unwrap();
+
+    assert_eq!(result.plugins.len(), 1);
+    // keyword 搜索与文本搜索各一条
+    assert_eq!(result.errors.len(), 2, "{:?}", result.errors);
+    for error in &result.errors {
+        assert_eq!(error.package_name, registry.url());
+        assert_eq!(error.error.code, ErrorCode::Http);
+        assert_eq!(error.error.context.status, Some(503));
+    }
+}
+
+#[tokio::test]
+async fn malformed_package_detail_is_reported_per_package() {
+    let registry = Registry::start();
+    let dir = TempDir::new("bad-detail");
+    let store = store_for(&registry, &dir).await;
+
+    registry.publish(
+        "usefultools-plugin-good",
+        &[&plugin_package("good", "1.0.0")],
+    );
+    registry.server.route(
+        "/usefultools-plugin-broken",
+        Response::json(200, "<html>not json</html>"),
+    );
+    registry.search(&["usefultools-plugin-good", "usefultools-plugin-broken"]);
+
+    let result = store.fetch_registry(true).await.unwrap();
+
+    let ids: Vec<&str> = result.plugins.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, ["good"]);
+    assert_eq!(result.errors.len(), 1);
+    assert_eq!(result.errors[0].package_name, "usefultools-plugin-broken");
+    assert_eq!(result.errors[0].error.code, ErrorCode::Parse);
+}
+
+#[tokio::test]
+async fn cache_with_plain_text_errors_is_refetched() {
+    let registry = Registry::start();
+    let dir = TempDir::new("legacy-cache");
+    let store = store_for(&registry, &dir).await;
+
+    registry.publish(
+        "usefultools-plugin-demo",
+        &[&plugin_package("demo", "1.0.0")],
+    );
+    registry.search(&["usefultools-plugin-demo"]);
+    // 旧版本写入的缓存尚未过期，但错误只保存了说明文字
+    let fetched_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    let legacy = json!({
+        "fetchedAt": fetched_at,
+        "ttl": 3_600_000,
+        "plugins": [],
+        "errors": [{ "packageName": "usefultools-plugin-old", "error": "解析失败" }],
+    });
+    std::fs::write(dir.0.join("registry-cache.json"), legacy.to_string()).unwrap();
+
+    let result = store.fetch_registry(false).await.unwrap();
+
+    let ids: Vec<&str> = result.plugins.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, ["demo"]);
+    assert!(result.errors.is_empty(), "{:?}", result.errors);
+}
+
+// ── plugin.json 诊断 ────────────────────────────────────────
+
+#[tokio::test]
+async fn registry_reports_warnings_and_invalid_manifests_per_package() {
+    let registry = Registry::start();
+    let dir = TempDir::new("manifest-registry");
+    let store = store_for(&registry, &dir).await;
+
+    let mut odd = tool_json("odd", "1.0.0");
+    odd["categories"] = json!(["奇怪的分类"]);
+    registry.publish("usefultools-plugin-odd", &[&package_with(&odd)]);
+    let mut bad = tool_json("bad", "1.0.0");
+    bad["bundle"] = json!("../bad.mjs");
+    registry.publish("usefultools-plugin-bad", &[&package_with(&bad)]);
+    registry.search(&["usefultools-plugin-odd", "usefultools-plugin-bad"]);
+
+    let result = store.fetch_registry(true).await.unwrap();
+
+    let ids: Vec<&str> = result.plugins.iter().map(|p| p.id.as_str()).collect();
+    assert_eq!(ids, ["odd"]);
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(result.warnings[0].package_name, "usefultools-plugin-odd");
+    let warning = &result.warnings[0].diagnostics[0];
+    assert_eq!(
+        (warning.severity, warning.path.as_str()),
+        (Severity::Warning, "$.categories[0]")
+    );
+
+    assert_eq!(result.errors.len(), 1);
+    let error = &result.errors[0].error;
+    assert_eq!(error.code, ErrorCode::InvalidManifest);
+    assert_eq!(error.context.diagnostics[0].path, "$.bundle");
+
+    // 缓存中的结果同样带有警告
+    let cached = store.fetch_registry(false).await.unwrap();
+    assert_eq!(cached.warnings.len(), 1);
+}
@@ -0,0 +1,227 @@
+mod common;
+
+use common::{append_file, build_tgz, TempDir};
+use serde_json::json;
+use std::io::{Read, Write};
+use usefultools_lib::error::ErrorCode;
+use usefultools_lib::plugin_manager::tarball::{
+    extract_file, TarballError, MAX_ENTRIES, MAX_ENTRY_SIZE, MAX_TARBALL_SIZE, MAX_UNPACKED_SIZE,
+};
+use usefultools_lib::plugin_manager::{validate_plugin_id, PluginStore};
+
+// ── 构造 tarball 的辅助函数 ─────────────────────────────────
+
+/// 绕过 tar crate 的路径检查，直接写入原始文件名
+fn append_raw_path(builder: &mut tar::Builder<impl Write>, raw_path: &str, data: &[u8]) {
+    let mut header = tar::Header::new_old();
+    header.set_size(data.len() as u64);
+    header.set_mode(0o644);
+    header.set_entry_type(tar::EntryType::Regular);
+    let name = &mut header.as_old_mut().name;
+    name[..raw_path.len()].copy_from_slice(raw_path.as_bytes());
+    header.set_cksum();
+    builder.append(&header, data).unwrap();
+}
+
+fn append_link(builder: &mut tar::Builder<impl Write>, path: &str, kind: tar::EntryType) {
+    let mut header = tar::Header::new_gnu();
+    header.set_entry_type(kind);
+    header.set_size(0);
+    builder
+        .append_link(&mut header, path, "/etc/passwd")
+        .unwrap();
+}
+
+// ── 正常包 ──────────────────────────────────────────────────
+
+#[test]
+fn extracts_target_file() {
+    let tgz = build_tgz(|b| {
+        append_file(b, "package/plugin.json", br#"{"id":"demo"}"#);
+        append_file(b, "package/dist/demo.mjs", b"export default {}");
+    });
+
+    let bundle = extract_file(&tgz, "package/dist/demo.mjs").unwrap();
+    assert_eq!(bundle.as_deref(), Some(&b"export default {}"[..]));
+    assert_eq!(extract_file(&tgz, "package/missing.mjs").unwrap(), None);
+}
+
+#[test]
+fn rejects_corrupt_gzip() {
+    let err = extract_file(b"definitely not gzip", "package/plugin.json").unwrap_err();
+    assert!(matches!(err, TarballError::Corrupt(_)), "{err:?}");
+}
+
+// ── 链接与路径穿越 ──────────────────────────────────────────
+
+#[test]
+fn rejects_symlink_entry() {
+    let tgz = build_tgz(|b| {
+        append_link(b, "package/dist/demo.mjs", tar::EntryType::Symlink);
+    });
+
+    let err = extract_file(&tgz, "package/dist/demo.mjs").unwrap_err();
+    assert_eq!(
+        err,
+        TarballError::Link {
+            path: "package/dist/demo.mjs".to_string()
+        }
+    );
+}
+
+#[test]
+fn rejects_hardlink_entry_even_when_not_requested() {
+    let tgz = build_tgz(|b| {
+        append_file(b, "package/plugin.json", b"{}");
+        append_link(b, "package/other", tar::EntryType::Link);
+    });
+
+    let err = extract_file(&tgz, "package/plugin.json").unwrap_err();
+    assert!(matches!(err, TarballError::Link { .. }), "{err:?}");
+}
+
+#[test]
+fn rejects_parent_dir_traversal() {
+    let tgz = build_tgz(|b| {
+        append_raw_path(b, "package/../../../.bashrc", b"echo pwned");
+    });
+
+    let err = extract_file(&tgz, "package/plugin.json").unwrap_err();
+    assert_eq!(
+        err,
+        TarballError::UnsafePath {
+            path: "package/../../../.bashrc".to_string()
+        }
+    );
+}
+
+#[test]
+fn rejects_absolute_path() {
+    let tgz = build_tgz(|b| {
+        append_raw_path(b, "/etc/cron.d/evil", b"* * * * * root sh");
+    });
+
+    let err = extract_file(&tgz, "package/plugin.json").unwrap_err();
+    assert!(matches!(err, TarballError::UnsafePath { .. }), "{err:?}");
+}
+
+/// 解压后的包目录中，经由链接目录读到包外的文件
+#[cfg(unix)]
+#[tokio::test]
+async fn rejects_package_directory_reading_through_a_linked_directory() {
+    let dir = TempDir::new("linked-dir");
+    let source = dir.0.join("source");
+    let outside = dir.0.join("outside");
+    std::fs::create_dir_all(&source).unwrap();
+    std::fs::create_dir_all(&outside).unwrap();
+    std::fs::write(outside.join("demo.mjs"), "export default 'outside'").unwrap();
+    std::os::unix::fs::symlink(&outside, source.join("dist")).unwrap();
+    let package_json = json!({ "name": "usefultools-plugin-demo", "version": "1.0.0" });
+    std::fs::write(source.join("package.json"), package_json.to_string()).unwrap();
+    let plugin_json = json!({
+        "id": "demo",
+        "version": "1.0.0",
+        "author": "tester",
+        "icon": "build",
+        "title": "Demo",
+        "subtitle": "test",
+        "description": "linked directory",
+        "bgColor": "bg-blue-500",
+        "categories": ["其他"],
+        "bundle": "dist/demo.mjs",
+    });
+    std::fs::write(source.join("plugin.json"), plugin_json.to_string()).unwrap();
+
+    let store = PluginStore::new(dir.0.join("plugins"));
+    let error = store
+        .install_plugin_from_file(source.to_string_lossy().to_string(), None)
+        .await
+        .unwrap_err();
+
+    assert_eq!(error.code, ErrorCode::InvalidPackage);
+    assert!(error.message.contains("dist/demo.mjs"), "{error}");
+    assert!(!dir.0.join("plugins/demo").exists());
+}
+
+// ── 大小与数量上限 ──────────────────────────────────────────
+
+#[test]
+fn rejects_oversized_tarball_before_decompressing() {
+    let tgz = vec![0u8; MAX_TARBALL_SIZE as usize + 1];
+
+    let err = extract_file(&tgz, "package/plugin.json").unwrap_err();
+    assert_eq!(
+        err,
+        TarballError::CompressedTooLarge {
+            size: MAX_TARBALL_SIZE + 1,
+            limit: MAX_TARBALL_SIZE
+        }
+    );
+}
+
+#[test]
+fn rejects_entry_declaring_huge_size() {
+    // 只写入头部：声明的大小超过上限时无需读取内容即可拒绝
+    let mut header = tar::Header::new_gnu();
+    header.set_path("package/dist/demo.mjs").unwrap();
+    header.set_size(MAX_ENTRY_SIZE + 1);
+    header.set_mode(0o644);
+    header.set_cksum();
+    let tgz = build_tgz(|b| b.get_mut().write_all(header.as_bytes()).unwrap());
+
+    let err = extract_file(&tgz, "package/dist/demo.mjs").unwrap_err();
+    assert!(matches!(err, TarballError::EntryTooLarge { .. }), "{err:?}");
+}
+
+#[test]
+fn rejects_zip_bomb() {
+    // 每个文件都低于单文件上限，但总大小超过解压上限
+    let entry_size = MAX_ENTRY_SIZE - 1024;
+    let count = MAX_UNPACKED_SIZE / entry_size + 1;
+    let tgz = build_tgz(|b| {
+        for i in 0..count {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(entry_size);
+            header.set_mode(0o644);
+            let zeros = std::io::repeat(0).take(entry_size);
+            b.append_data(&mut header, format!("package/zero-{i}.bin"), zeros)
+                .unwrap();
+        }
+    });
+    assert!((tgz.len() as u64) < MAX_UNPACKED_SIZE / 100);
+
+    let err = extract_file(&tgz, "package/plugin.json").unwrap_err();
+    assert_eq!(
+        err,
+        TarballError::TooLarge {
+            limit: MAX_UNPACKED_SIZE
+        }
+    );
+}
+
+#[test]
+fn rejects_too_many_entries() {
+    let tgz = build_tgz(|b| {
+        for i in 0..=MAX_ENTRIES {
+            append_file(b, &format!("package/f{i}"), b"");
+        }
+    });
+
+    let err = extract_file(&tgz, "package/plugin.json").unwrap_err();
+    assert_eq!(err, TarballError::TooManyEntries { limit: MAX_ENTRIES });
+}
+
+// ── 插件 id ─────────────────────────────────────────────────
+
+#[test]
+fn plugin_id_validation() {
+    for id in ["json-formatter", "base64", "Tool_2"] {
+        assert!(validate_plugin_id(id).is_ok(), "{id}");
+    }
+    for id in [
+        "", "..", "../..", ".staging", "a/b", "a\\b", "-lead", "C:", "名字",
+    ] {
+        assert!(validate_plugin_id(id).is_err(), "{id}");
+    }
+    assert!(validate_plugin_id(&"a".repeat(65)).is_err());
+}
@@ -84,6 +84,60 @@ function rewriteEsmToFunction(source: string): string {
 
 // ── 类型定义 ──────────────────────────────────────────────
 
+/** 命令错误的分类（与 Rust 端 ErrorCode 一致） */
+export type ErrorCode =
+  | 'network' | 'http' | 'unauthorized' | 'not_found'
+  | 'integrity' | 'invalid_package' | 'invalid_manifest' | 'parse'
+  | 'invalid_input' | 'dependency' | 'disabled' | 'busy'
+  | 'cancelled' | 'io' | 'forbidden' | 'internal'
+
+/** plugin.json 校验的单条诊断 */
+export interface ManifestDiagnostic {
+  severity: 'error' | 'warning'
+  /** JSON 路径（如 $.plugins[0].id） */
+  path: string
+  message: string
+}
+
+/** Tauri 命令失败时返回的错误 */
+export interface AppError {
+  code: ErrorCode
+  message: string
+  context: {
+    package?: string
+    pluginId?: string
+    url?: string
+    /** registry 返回的 HTTP 状态码 */
+    status?: number
+    /** plugin.json 校验的全部错误与警告 */
+    diagnostics?: ManifestDiagnostic[]
+  }
+}
+
+export function isAppError(err: unknown): err is AppError {
+  return typeof err === 'object' && err !== null
+    && typeof (err as AppError).code === 'string'
+    && typeof (err as AppError).message === 'string'
+}
+
+/** 取得任意错误的说明文字 */
+export function errorMessage(err: unknown): string {
+  if (typeof err === 'string') return err
+  if (isAppError(err) || err instanceof Error) return err.message
+  return String(err)
+}
+
+/** 把任意错误转为 AppError（非命令错误归为 internal） */
+export function toAppError(err: unknown): AppError {
+  if (isAppError(err)) return err
+  return { code: 'internal', message: errorMessage(err), context: {} }
+}
+
+/** 是否为指定分类的命令错误 */
+export function hasErrorCode(err: unknown, ...codes: ErrorCode[]): err is AppError {
+  return isAppError(err) && codes.includes(err.code)
+}
+
 export type PluginCapability =
   | 'fs:read' | 'fs:write' | 'http' | 'dialog'
   | 'clipboard' | 'system:info' | 'system:process'
@@ -102,8 +156,16 @@ export interface PluginMeta {
   textColor?: string
   categories: string[]
   requires: PluginCapability[]
+  /** 依赖的其他插件 id */
+  dependencies?: string[]
   /** npm 包名 */
   packageName: string
+  /** 插件来自的 registry 地址 */
+  registry?: string
+  /** 非 registry 的安装来源（从本地文件安装时为 "local file"） */
+  origin?: string
+  /** 安装时 tarball 的 SRI 哈希 */
+  integrity?: string
   /** bundle 文件路径（如 dist/my-tool.mjs） */
   bundleFile: string
   downloads?: number
@@ -112,18 +174,98 @@ export interface PluginMeta {
   createdAt?: string
 }
 
+/** 注册表刷新时解析失败的包 */
+export interface PackageError {
+  /** 包名；registry 搜索失败或结果不完整时为该 registry 的地址 */
+  packageName: string
+  error: AppError
+}
+
+/** plugin.json 中有警告的包（包中的插件仍可使用） */
+export interface PackageWarning {
+  packageName: string
+  diagnostics: ManifestDiagnostic[]
+}
+
 export interface InstalledPlugin {
   meta: PluginMeta
+  /** 首次安装时间 */
   installedAt: number
+  /** 最近一次安装、更新或回滚的时间 */
   updatedAt: number
+  /** 最近一次更新前的版本 */
+  previousVersion?: string
   localBundlePath: string
   enabled: boolean
+  /** 锁定的版本 */
+  pinnedVersion?: string
+  /** 可回滚到的上一个版本 */
+  backupVersion?: string
+}
+
+/** registry 的认证方式（与 Rust 端 RegistryAuth 一致） */
+export type RegistryAuth =
+  | { type: 'bearer'; token: string }
+  | { type: 'basic'; username: string; password: string }
+
+/** 已保存凭据的 registry（不含密钥） */
+export interface RegistryAuthSummary {
+  /** nerf dart 形式的 registry（如 //npm.example.com/） */
+  registry: string
+  kind: 'bearer' | 'basic'
+  username?: string
+}
+
+/** list_registry_auth 的返回值 */
+export interface RegistryAuthList {
+  registries: RegistryAuthSummary[]
+  /** 凭据文件无法还原时的原因（此时列表为空） */
+  warning?: AppError
+}
+
+/** 锁文件中锁定的插件 */
+export interface LockedPlugin {
+  id: string
+  packageName: string
+  version: string
+  registry?: string
+  integrity?: string
+  origin?: string
+  /** 打包的 tarball（相对锁文件所在目录） */
+  tarball?: string
+}
+
+/** 启动时恢复中断安装的结果 */
+export interface RecoveryReport {
+  /** 恢复为上一个完整版本的插件 */
+  restored: string[]
+  /** 无法恢复、已删除的插件目录 */
+  removed: string[]
+  discardedStaging: number
+}
+
+/** 导入锁文件的结果（dryRun 时只有 diff） */
+export interface LockfileImportResult {
+  diff: {
+    added: LockedPlugin[]
+    removed: LockedPlugin[]
+    changed: { id: string; from: LockedPlugin; to: LockedPlugin }[]
+  }
+  dryRun: boolean
+  installed: InstalledPlugin[]
+  removed: string[]
+  failed: { pluginId: string; error: AppError }[]
 }
 
 // ── 模块级响应式状态（单例） ──────────────────────────────
 
 const installedPlugins: Ref<InstalledPlugin[]> = ref([])
 const registryPlugins: Ref<PluginMeta[]> = ref([])
+const registryErrors: Ref<PackageError[]> = ref([])
+const registryWarnings: Ref<PackageWarning[]> = ref([])
+/** 最近一次刷新注册表整体失败的原因（如离线、registry 拒绝访问） */
+const registryError: Ref<AppError | null> = ref(null)
+const recoveryReport: Ref<RecoveryReport | null> = ref(null)
 const isLoadingRegistry: Ref<boolean> = ref(false)
 const isInstalling: Ref<Set<string>> = ref(new Set())
 const initialized: Ref<boolean> = ref(false)
@@ -207,17 +349,33 @@ async function initialize(): Promise<void> {
   } finally {
     initialized.value = true
   }
+
+  try {
+    recoveryReport.value = await invoke<RecoveryReport | null>('take_plugin_recovery_report')
+  } catch (err) {
+    console.error('读取安装恢复结果失败:', err)
+  }
+}
+
+function dismissRecoveryReport(): void {
+  recoveryReport.value = null
 }
 
 async function fetchRegistry(force?: boolean): Promise<void> {
   isLoadingRegistry.value = true
   try {
-    const plugins = await invoke<PluginMeta[]>('fetch_plugin_registry', {
-      forceRefresh: force ?? false,
-    })
-    registryPlugins.value = plugins
+    const result = await invoke<{
+      plugins: PluginMeta[]
+      errors: PackageError[]
+      warnings: PackageWarning[]
+    }>('fetch_plugin_registry', { forceRefresh: force ?? false })
+    registryPlugins.value = result.plugins
+    registryErrors.value = result.errors
+    registryWarnings.value = result.warnings
+    registryError.value = null
   } catch (err) {
-    console.error('获取插件注册表失败:', err)
+    // 保留上次的列表，由界面按 code 提示登录或重试
+    registryError.value = toAppError(err)
   } finally {
     isLoadingRegistry.value = false
   }
@@ -231,11 +389,21 @@ async function installPlugin(meta: PluginMeta): Promise<void> {
   isInstalling.value = new Set([...isInstalling.value, meta.id])
 
   try {
-    const installed = await invoke<InstalledPlugin>('install_plugin', { plugin: meta })
-    installedPlugins.value = [...installedPlugins.value, installed]
-    registerPluginRoute(installed)
+    await invoke<InstalledPlugin>('install_plugin', { plugin: meta })
+    // 依赖插件会一并安装，重新读取已安装列表
+    const plugins = await invoke<InstalledPlugin[]>('get_installed_plugins')
+    for (const plugin of plugins) {
+      if (!isInstalled(plugin.meta.id) && plugin.enabled) {
+        registerPluginRoute(plugin)
+      }
+    }
+    installedPlugins.value = plugins
   } catch (err) {
     console.error(`安装插件 ${meta.id} 失败:`, err)
+    // 包已从 registry 删除：从列表中移除，避免再次安装
+    if (hasErrorCode(err, 'not_found') && err.context.package === meta.packageName && !err.context.pluginId) {
+      registryPlugins.value = registryPlugins.value.filter(p => p.packageName !== meta.packageName)
+    }
     throw err
   } finally {
     const next = new Set(isInstalling.value)
@@ -244,18 +412,97 @@ async function installPlugin(meta: PluginMeta): Promise<void> {
   }
 }
 
+/** 从本地 .tgz 或包目录安装插件（离线环境使用） */
+async function installFromFile(path: string, pluginIds?: string[]): Promise<InstalledPlugin[]> {
+  const result = await invoke<InstalledPlugin[]>('install_plugin_from_file', { path, pluginIds })
+  const plugins = await invoke<InstalledPlugin[]>('get_installed_plugins')
+  for (const plugin of result) {
+    // 覆盖安装时清除旧组件缓存
+    componentCache.delete(plugin.meta.id)
+    removePluginRoute(plugin.meta.id)
+  }
+  for (const plugin of plugins) {
+    if ((!isInstalled(plugin.meta.id) || result.some(r => r.meta.id === plugin.meta.id)) && plugin.enabled) {
+      registerPluginRoute(plugin)
+    }
+  }
+  installedPlugins.value = plugins
+  return result
+}
+
+/** 导出已安装插件的锁文件，bundle 为 true 时一并打包 tarball */
+async function exportLockfile(path: string, bundle = false): Promise<void> {
+  await invoke('export_plugin_lockfile', { path, bundle })
+}
+
+/** 按锁文件同步已安装插件；dryRun 时只返回差异 */
+async function importLockfile(path: string, dryRun = false): Promise<LockfileImportResult> {
+  const result = await invoke<LockfileImportResult>('import_plugin_lockfile', { path, dryRun })
+  if (dryRun) return result
+
+  for (const id of [...result.removed, ...result.installed.map(p => p.meta.id)]) {
+    componentCache.delete(id)
+    removePluginRoute(id)
+  }
+  const plugins = await invoke<InstalledPlugin[]>('get_installed_plugins')
+  for (const plugin of plugins) {
+    if (plugin.enabled && (!isInstalled(plugin.meta.id) || result.installed.some(p => p.meta.id === plugin.meta.id))) {
+      registerPluginRoute(plugin)
+    }
+  }
+  installedPlugins.value = plugins
+  return result
+}
+
+/** 启用或停用已安装的插件（停用后保留安装文件，但不再加载） */
+async function setPluginEnabled(id: string, enabled: boolean): Promise<void> {
+  const updated = await invoke<InstalledPlugin>('set_plugin_enabled', { pluginId: id, enabled })
+  componentCache.delete(id)
+  removePluginRoute(id)
+  if (updated.enabled) {
+    registerPluginRoute(updated)
+  }
+  installedPlugins.value = installedPlugins.value.map(p => (p.meta.id === id ? updated : p))
+}
+
 async function uninstallPlugin(id: string): Promise<void> {
   try {
-    await invoke('uninstall_plugin', { pluginId: id })
-    removePluginRoute(id)
-    componentCache.delete(id)
-    installedPlugins.value = installedPlugins.value.filter(p => p.meta.id !== id)
+    const removed = await invoke<string[]>('uninstall_plugin', { pluginId: id })
+    for (const removedId of removed) {
+      removePluginRoute(removedId)
+      componentCache.delete(removedId)
+    }
+    installedPlugins.value = installedPlugins.value.filter(p => !removed.includes(p.meta.id))
   } catch (err) {
     console.error(`卸载插件 ${id} 失败:`, err)
     throw err
   }
 }
 
+/** 列出已保存凭据的 registry */
+async function listRegistryAuth(): Promise<RegistryAuthList> {
+  return invoke<RegistryAuthList>('list_registry_auth')
+}
+
+/** 保存 registry 凭据（auth 为 null 时删除），之后重新刷新注册表 */
+async function setRegistryAuth(registry: string, auth: RegistryAuth | null): Promise<void> {
+  await invoke('set_registry_auth', { registry, auth })
+  await fetchRegistry(true)
+}
+
+/** 删除全部 registry 凭据（包括无法还原的凭据文件），之后重新刷新注册表 */
+async function clearRegistryAuth(): Promise<void> {
+  await invoke('clear_registry_auth')
+  await fetchRegistry(true)
+}
+
+/** 从 ~/.npmrc 导入凭据，返回导入的 registry */
+async function importNpmrcAuth(): Promise<string[]> {
+  const imported = await invoke<string[]>('import_npmrc_auth')
+  if (imported.length > 0) await fetchRegistry(true)
+  return imported
+}
+
 function isInstalled(id: string): boolean {
   return installedPlugins.value.some(p => p.meta.id === id)
 }
@@ -337,16 +584,37 @@ async function loadPluginComponent(plugin: InstalledPlugin): Promise<any> {
   } catch (err) {
     console.error(`加载插件组件 ${id} 失败:`, err)
 
-    // 返回错误占位组件
+    // 返回错误占位组件：停用的插件可直接启用，文件缺失时提示重新安装
+    const disabled = hasErrorCode(err, 'disabled')
+    const missing = hasErrorCode(err, 'not_found')
+    const title = disabled
+      ? `插件 "${id}" 已停用`
+      : missing
+        ? `插件 "${id}" 的文件已丢失`
+        : `插件 "${id}" 加载失败`
+    const hint = missing ? '请在插件商店中卸载后重新安装' : errorMessage(err)
+
+    async function enable() {
+      await setPluginEnabled(id, true)
+      // 路由已替换为新的组件，重新进入当前页面
+      await router.replace({ path: `/${id}`, force: true })
+    }
+
     return {
       name: 'PluginLoadError',
       setup() {
         return () => h('div', {
-          class: 'flex flex-col items-center justify-center h-full text-red-400 gap-4',
+          class: `flex flex-col items-center justify-center h-full gap-4 ${disabled ? 'text-white/60' : 'text-red-400'}`,
         }, [
-          h('span', { class: 'material-icons text-5xl' }, 'error_outline'),
-          h('p', { class: 'text-lg' }, `插件 "${id}" 加载失败`),
-          h('p', { class: 'text-sm text-gray-500' }, String(err)),
+          h('span', { class: 'material-icons text-5xl' }, disabled ? 'block' : 'error_outline'),
+          h('p', { class: 'text-lg' }, title),
+          h('p', { class: 'text-sm text-gray-500' }, hint),
+          disabled
+            ? h('button', {
+              class: 'px-4 py-2 rounded-lg border-2 border-black bg-neon-green text-black font-bold text-xs shadow-hard-sm hover:shadow-none hover:translate-x-[2px] hover:translate-y-[2px] transition-all',
+              onClick: () => enable().catch(e => console.error(`启用插件 ${id} 失败:`, e)),
+            }, '启用插件')
+            : null,
         ])
       },
     }
@@ -357,11 +625,14 @@ async function loadPluginComponent(plugin: InstalledPlugin): Promise<any> {
 
 const debugPlugins: Ref<InstalledPlugin[]> = ref([])
 const isLoadingDebug: Ref<boolean> = ref(false)
+/** 最近一次加载调试插件时 plugin.json 的错误与警告 */
+const debugDiagnostics: Ref<ManifestDiagnostic[]> = ref([])
 
 /** 从本地目录加载调试插件 */
 async function loadDebugPlugins(dirPath: string): Promise<void> {
   if (!dirPath.trim()) return
   isLoadingDebug.value = true
+  debugDiagnostics.value = []
 
   try {
     // 先移除旧的调试插件路由
@@ -371,7 +642,11 @@ async function loadDebugPlugins(dirPath: string): Promise<void> {
     }
 
     // 读取本地 plugin.json
-    const metas = await invoke<PluginMeta[]>('read_local_plugin_json', { dirPath })
+    const { plugins: metas, warnings } = await invoke<{
+      plugins: PluginMeta[]
+      warnings: ManifestDiagnostic[]
+    }>('read_local_plugin_json', { dirPath })
+    debugDiagnostics.value = warnings
 
     const now = Date.now()
     const plugins: InstalledPlugin[] = metas.map(meta => ({
@@ -391,6 +666,7 @@ async function loadDebugPlugins(dirPath: string): Promise<void> {
     }
   } catch (err) {
     console.error('加载调试插件失败:', err)
+    if (isAppError(err)) debugDiagnostics.value = err.context.diagnostics ?? []
     throw err
   } finally {
     isLoadingDebug.value = false
@@ -404,6 +680,7 @@ function unloadDebugPlugins() {
     componentCache.delete(dp.meta.id)
   }
   debugPlugins.value = []
+  debugDiagnostics.value = []
 }
 
 /** 重新加载单个调试插件（清除缓存） */
@@ -424,19 +701,33 @@ export function usePluginStore() {
     // 状态
     installedPlugins,
     registryPlugins,
+    registryErrors,
+    registryWarnings,
+    registryError,
+    recoveryReport,
     isLoadingRegistry,
     isInstalling,
     initialized,
     debugPlugins,
     isLoadingDebug,
+    debugDiagnostics,
     // 计算属性
     activeTools,
     allCategories,
     // 方法
     initialize,
     fetchRegistry,
+    dismissRecoveryReport,
     installPlugin,
+    installFromFile,
+    exportLockfile,
+    importLockfile,
+    setPluginEnabled,
     uninstallPlugin,
+    listRegistryAuth,
+    setRegistryAuth,
+    clearRegistryAuth,
+    importNpmrcAuth,
     isInstalled,
     isPluginInstalling,
     loadPluginComponent,